@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::OnceCell;
+
+use crate::modules::hashivault::client::HashivaultClient;
+use crate::modules::aws::client::AwsClient;
+use crate::modules::gcloud::client::GCloudClient;
+use crate::modules::azure::client::AzureClient;
+
+pub type SharedHashivaultClient = Arc<dyn HashivaultClient + Send + Sync>;
+pub type SharedAwsClient = Arc<dyn AwsClient + Send + Sync>;
+pub type SharedGCloudClient = Arc<dyn GCloudClient + Send + Sync>;
+pub type SharedAzureClient = Arc<dyn AzureClient + Send + Sync>;
+
+/// AWS clients are keyed by backend name, profile override and access key ID of environment session credentials they use
+type AwsClientKey = (Option<String>, Option<String>, Option<String>);
+
+#[derive(Default)]
+struct Clients {
+    /// Vault clients by backend name (None for default config)
+    hashivault: OnceMap<Option<String>, SharedHashivaultClient>,
+
+    /// AWS clients by backend name, profile override (None for default config) and session credentials
+    aws: OnceMap<AwsClientKey, SharedAwsClient>,
+
+    gcloud: OnceCell<SharedGCloudClient>,
+
+    azure: OnceCell<SharedAzureClient>,
+}
+
+/**
+ * Clients built while loading an environment, shared among Inputs resolved in parallel.
+ *
+ * Each client is built (and authenticated) once per backend on first use,
+ * avoiding a new Vault login or AWS credentials resolution for each Input.
+ * Cloning the registry shares the same clients.
+ */
+#[derive(Clone, Default)]
+pub struct ClientRegistry {
+    clients: Arc<Clients>
+}
+
+impl ClientRegistry {
+
+    /// Get Vault client for backend, building it with `build` if it doesn't exist yet
+    pub async fn hashivault<F, Fut>(&self, backend: &Option<String>, build: F) -> Result<SharedHashivaultClient, anyhow::Error>
+    where F: FnOnce() -> Fut, Fut: Future<Output = Result<SharedHashivaultClient, anyhow::Error>> {
+        self.clients.hashivault.get_or_try_init(backend.clone(), build).await
+    }
+
+    /// Get AWS client for backend and profile, building it with `build` if it doesn't exist yet.
+    /// Clients are kept apart by session credentials access key ID, as chained credentials 
+    /// (such as Hashivault credentials used to assume a role) change session credentials while loading.
+    pub async fn aws<F, Fut>(&self, backend: &Option<String>, profile: &Option<String>, session_access_key_id: &Option<String>, build: F) -> Result<SharedAwsClient, anyhow::Error>
+    where F: FnOnce() -> Fut, Fut: Future<Output = Result<SharedAwsClient, anyhow::Error>> {
+        self.clients.aws.get_or_try_init((backend.clone(), profile.clone(), session_access_key_id.clone()), build).await
+    }
+
+    /// Get Google Cloud client, building it with `build` if it doesn't exist yet
+    pub async fn gcloud<F, Fut>(&self, build: F) -> Result<SharedGCloudClient, anyhow::Error>
+    where F: FnOnce() -> Fut, Fut: Future<Output = Result<SharedGCloudClient, anyhow::Error>> {
+        self.clients.gcloud.get_or_try_init(build).await.cloned()
+    }
+
+    /// Get Azure client, building it with `build` if it doesn't exist yet
+    pub async fn azure<F, Fut>(&self, build: F) -> Result<SharedAzureClient, anyhow::Error>
+    where F: FnOnce() -> Fut, Fut: Future<Output = Result<SharedAzureClient, anyhow::Error>> {
+        self.clients.azure.get_or_try_init(build).await.cloned()
+    }
+}
+
+/**
+ * Values computed at most once per key, such as clients or secrets read from a remote backend.
+ * 
+ * Concurrent calls for the same key wait for a single computation whose result is shared. 
+ * A failed computation is not kept and will be retried on next call.
+ */
+pub struct OnceMap<K, V> {
+    cells: Mutex<HashMap<K, Arc<OnceCell<V>>>>
+}
+
+impl<K: Eq + Hash, V: Clone> OnceMap<K, V> {
+
+    /// Get value for key or compute it with `init`
+    pub async fn get_or_try_init<F, Fut>(&self, key: K, init: F) -> Result<V, anyhow::Error>
+    where F: FnOnce() -> Fut, Fut: Future<Output = Result<V, anyhow::Error>> {
+        self.cell(key)?.get_or_try_init(init).await.cloned()
+    }
+
+    /// Set value for key, unless a value is already set
+    pub fn insert(&self, key: K, value: V) -> Result<(), anyhow::Error> {
+        // value already set by another call is kept
+        let _ = self.cell(key)?.set(value);
+        Ok(())
+    }
+
+    /// Whether a value is set for key
+    pub fn contains(&self, key: K) -> Result<bool, anyhow::Error> {
+        Ok(self.cell(key)?.initialized())
+    }
+
+    fn cell(&self, key: K) -> Result<Arc<OnceCell<V>>, anyhow::Error> {
+        // only hold lock to get cell, not while computing value
+        let cell = self.cells.lock()
+            .map_err(|e| anyhow::anyhow!("OnceMap lock poisoned: {:?}", e))?
+            .entry(key)
+            .or_default()
+            .clone();
+        Ok(cell)
+    }
+}
+
+impl<K, V> Default for OnceMap<K, V> {
+    fn default() -> Self {
+        OnceMap { cells: Mutex::new(HashMap::new()) }
+    }
+}
+
+/// Clients are a runtime cache and are not part of context equality
+impl PartialEq for ClientRegistry {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl fmt::Debug for ClientRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientRegistry").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn test_once_map_computes_once_per_key() {
+        let map: Arc<OnceMap<String, String>> = Arc::new(OnceMap::default());
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let mut tasks = tokio::task::JoinSet::new();
+        for i in 0..10 {
+            let (map, calls) = (map.clone(), calls.clone());
+            let key = if i % 2 == 0 { "even" } else { "odd" };
+            tasks.spawn(async move {
+                map.get_or_try_init(key.to_string(), || async {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+                    Ok(format!("value-{}", key))
+                }).await
+            });
+        }
+
+        while let Some(res) = tasks.join_next().await {
+            assert!(res.unwrap().unwrap().starts_with("value-"));
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // failures are not kept, inserted values are used as-is
+        let failed = map.get_or_try_init("failed".to_string(), || async { Err(anyhow::anyhow!("failed")) }).await;
+        assert!(failed.is_err());
+        assert!(!map.contains("failed".to_string()).unwrap());
+
+        map.insert("prefetched".to_string(), "batch".to_string()).unwrap();
+        let prefetched = map.get_or_try_init("prefetched".to_string(), || async { Ok("single".to_string()) }).await.unwrap();
+        assert_eq!(prefetched, "batch");
+    }
+}
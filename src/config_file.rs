@@ -0,0 +1,254 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use log::debug;
+use serde_yaml::Value;
+
+use crate::core::NovopsConfigFile;
+
+/// Root key used to include other config files
+const INCLUDE_KEY: &str = "include";
+
+/**
+ * Raw YAML config merged from one or more files.
+ * Keep track of the file each value originates from to provide meaningful errors.
+ */
+struct MergedConfig {
+    value: Value,
+
+    /// Origin file for values, keyed by dotted path such as `environments.dev.variables`.
+    /// Empty path is the origin of the whole config.
+    origins: HashMap<String, PathBuf>
+}
+
+/**
+ * Read and merge Novops config files in order, later files taking precedence over previous ones.
+ *
+ * Each file's `include` are loaded and merged first (relative to the including file, globs allowed),
+ * then including file is merged on top. Included files must not define conflicting values.
+ */
+pub fn read_config_files(config_paths: &[PathBuf]) -> Result<NovopsConfigFile, anyhow::Error> {
+
+    let mut merged: Option<MergedConfig> = None;
+
+    for cfg_path in config_paths {
+        let cfg = load_config_with_includes(cfg_path, &mut vec![])?;
+
+        merged = match merged {
+            Some(mut m) => {
+                m.merge(cfg, false)?;
+                Some(m)
+            },
+            None => Some(cfg),
+        };
+    }
+
+    let merged = merged.ok_or(anyhow::anyhow!("No config file provided."))?;
+
+    let config: NovopsConfigFile = serde_yaml::from_value(merged.value)
+        .with_context(|| format!("Error parsing config merged from {:?}. Does it match expected config schema?", config_paths))?;
+
+    Ok(config)
+}
+
+/**
+ * Load a config file and its included files, recursively.
+ * `chain` holds files currently being loaded to detect include cycles.
+ */
+fn load_config_with_includes(cfg_path: &Path, chain: &mut Vec<PathBuf>) -> Result<MergedConfig, anyhow::Error> {
+
+    debug!("Loading config file path '{:?}'", &cfg_path);
+
+    let canonical_path = fs::canonicalize(cfg_path)
+        .with_context(|| format!("Failed to open Novops config {:?}", &cfg_path))?;
+
+    if chain.contains(&canonical_path) {
+        let chain_str = chain.iter()
+            .map(|p| format!("{:?}", p))
+            .collect::<Vec<String>>()
+            .join(" -> ");
+        return Err(anyhow::anyhow!("Config file {:?} includes itself via {} -> {:?}", &cfg_path, chain_str, &canonical_path));
+    }
+
+    let f = fs::File::open(cfg_path)
+        .with_context(|| format!("Failed to open Novops config {:?}", &cfg_path))?;
+    let mut value: Value = serde_yaml::from_reader(f)
+        .with_context(|| format!("Error parsing config file {:?}. Is it valid YAML?", &cfg_path))?;
+
+    // Each file must be a valid config on its own
+    // so that schema errors are reported for the originating file
+    let config: NovopsConfigFile = serde_yaml::from_value(value.clone())
+        .with_context(|| format!("Error parsing config file {:?}. Does it match expected config schema?", &cfg_path))?;
+
+    if let Value::Mapping(m) = &mut value {
+        m.remove(&Value::from(INCLUDE_KEY));
+    }
+
+    let file_config = MergedConfig {
+        value,
+        origins: HashMap::from([(String::new(), cfg_path.to_path_buf())])
+    };
+
+    let includes = config.include.unwrap_or_default();
+    if includes.is_empty() {
+        return Ok(file_config);
+    }
+
+    chain.push(canonical_path);
+
+    let mut merged_includes: Option<MergedConfig> = None;
+    for include_path in list_included_files(cfg_path, &includes)? {
+        let included = load_config_with_includes(&include_path, chain)
+            .with_context(|| format!("Failed to load config {:?} included by {:?}", &include_path, &cfg_path))?;
+
+        merged_includes = match merged_includes {
+            Some(mut m) => {
+                m.merge(included, true)
+                    .with_context(|| format!("Conflicting definitions in config files included by {:?}", &cfg_path))?;
+                Some(m)
+            },
+            None => Some(included),
+        };
+    }
+
+    chain.pop();
+
+    match merged_includes {
+        Some(mut m) => {
+            m.merge(file_config, false)?;
+            Ok(m)
+        },
+        None => Ok(file_config)
+    }
+}
+
+/**
+ * List files included by config file. Include patterns are relative to config file directory.
+ * Glob matches are sorted for a deterministic order.
+ */
+fn list_included_files(cfg_path: &Path, includes: &[String]) -> Result<Vec<PathBuf>, anyhow::Error> {
+    let base_dir = cfg_path.parent().unwrap_or(Path::new("."));
+
+    let mut result = vec![];
+    for include in includes {
+        let pattern_path = base_dir.join(include);
+        let pattern = pattern_path.to_str()
+            .ok_or(anyhow::anyhow!("Couldn't convert include path '{:?}' to String", &pattern_path))?;
+
+        let mut matches = glob::glob(pattern)
+            .with_context(|| format!("Invalid include pattern '{}' in {:?}", include, &cfg_path))?
+            .collect::<Result<Vec<PathBuf>, glob::GlobError>>()
+            .with_context(|| format!("Couldn't read files matching include '{}' in {:?}", include, &cfg_path))?;
+
+        // a plain path (without glob pattern) must exist
+        if matches.is_empty() && pattern == glob::Pattern::escape(pattern) {
+            return Err(anyhow::anyhow!("Config file {:?} included by {:?} not found", &pattern_path, &cfg_path));
+        }
+
+        matches.sort();
+        result.extend(matches);
+    }
+
+    Ok(result)
+}
+
+impl MergedConfig {
+
+    /**
+     * Deep merge other config into this one. Mappings are merged recursively,
+     * other values (strings, lists...) are replaced by other's value.
+     *
+     * If `fail_on_conflict` is true, fail instead of replacing a value by a different one.
+     */
+    fn merge(&mut self, other: MergedConfig, fail_on_conflict: bool) -> Result<(), anyhow::Error> {
+        merge_value(&mut self.value, other.value, "", &mut self.origins, &other.origins, fail_on_conflict)
+    }
+}
+
+fn merge_value(base: &mut Value, other: Value, path: &str,
+    base_origins: &mut HashMap<String, PathBuf>, other_origins: &HashMap<String, PathBuf>,
+    fail_on_conflict: bool
+) -> Result<(), anyhow::Error> {
+
+    match (base, other) {
+        (Value::Mapping(base_map), Value::Mapping(other_map)) => {
+            for (k, v) in other_map {
+                let key_path = join_path(path, &k);
+
+                match base_map.get_mut(&k) {
+                    Some(base_v) => merge_value(base_v, v, &key_path, base_origins, other_origins, fail_on_conflict)?,
+                    None => {
+                        copy_origins(&key_path, base_origins, other_origins);
+                        base_map.insert(k, v);
+                    }
+                }
+            }
+        },
+        (base, other) => {
+            if fail_on_conflict && *base != other {
+                return Err(anyhow::anyhow!("'{}' is defined with different values in {:?} and {:?}",
+                    path, origin_of(path, base_origins).unwrap_or_default(), origin_of(path, other_origins).unwrap_or_default()));
+            }
+
+            base_origins.retain(|p, _| !is_sub_path(p, path));
+            copy_origins(path, base_origins, other_origins);
+            *base = other;
+        }
+    }
+
+    Ok(())
+}
+
+/// Copy origins of value at path (and its children)
+fn copy_origins(path: &str, base_origins: &mut HashMap<String, PathBuf>, other_origins: &HashMap<String, PathBuf>) {
+    if let Some(origin) = origin_of(path, other_origins) {
+        base_origins.insert(path.to_string(), origin);
+    }
+
+    for (p, origin) in other_origins {
+        if is_sub_path(p, path) {
+            base_origins.insert(p.clone(), origin.clone());
+        }
+    }
+}
+
+/// Origin of value at path, which is the origin of the nearest parent path with a known origin
+fn origin_of(path: &str, origins: &HashMap<String, PathBuf>) -> Option<PathBuf> {
+    let mut current = path;
+    loop {
+        if let Some(o) = origins.get(current) {
+            return Some(o.clone());
+        }
+
+        match current.rfind('.') {
+            Some(idx) => current = &current[..idx],
+            None if !current.is_empty() => current = "",
+            None => return None
+        }
+    }
+}
+
+/// Whether path is a sub path of parent, such as `environments.dev` for `environments`
+fn is_sub_path(path: &str, parent: &str) -> bool {
+    if parent.is_empty() {
+        return !path.is_empty()
+    }
+
+    path.starts_with(parent) && path[parent.len()..].starts_with('.')
+}
+
+fn join_path(path: &str, key: &Value) -> String {
+    let key_str = match key {
+        Value::String(s) => s.clone(),
+        other => format!("{:?}", other)
+    };
+
+    if path.is_empty() {
+        key_str
+    } else {
+        format!("{}.{}", path, key_str)
+    }
+}
+
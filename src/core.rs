@@ -5,6 +5,8 @@ use anyhow;
 use std::path::PathBuf;
 use schemars::JsonSchema;
 
+use crate::clients::ClientRegistry;
+use crate::retry::RetryConfig;
 use crate::modules::aws::config::AwsConfig;
 use crate::modules::hashivault::{
     self,
@@ -19,6 +21,11 @@ use crate::modules::azure;
 use crate::modules::files::FileInput;
 use crate::modules::variables::VariableInput;
 use crate::modules::sops;
+use crate::modules::template;
+use crate::modules::first_of;
+use crate::modules::json;
+use crate::modules::transform;
+use aws_sdk_sts::config::Credentials;
 
 /// Available environments. Keys are environment names. 
 type NovopsEnvironments = HashMap<String, NovopsEnvironmentInput>;
@@ -33,10 +40,18 @@ pub struct NovopsConfigFile {
     /// If not specified, use current directory name
     pub name: Option<String>,
 
+    /// Other config files to include, such as a config shared across an organization. 
+    /// Paths are relative to this config file and may use glob patterns, such as `../shared/*.yml`. 
+    /// 
+    /// Included files are deep-merged with this config file, this config file taking precedence. 
+    /// Included files must not define conflicting values.
+    pub include: Option<Vec<String>>,
+
     /// Source of truth defining files and variables loaded by Novops
     /// 
     /// Environments are named uniquely (such as "dev", "prod"...) 
     /// to allow for different configs to be loaded in various contexts
+    #[serde(default)]
     pub environments: NovopsEnvironments,
 
     /// Global configurations for Novops and modules 
@@ -55,19 +70,133 @@ pub struct NovopsConfig {
     pub hashivault: Option<HashivaultConfig>,
 
     /// AWS module configs
-    pub aws: Option<AwsConfig>
+    pub aws: Option<AwsConfig>,
+
+    /// Named module configs, for Inputs to use another backend than default module config via `backend: <name>`
+    pub backends: Option<NovopsBackendsConfig>
+}
+
+/// Named module configs. Only Hashicorp Vault and AWS modules support named backends: 
+/// Azure and Google Cloud modules have no module config and use credentials available in environment.
+#[derive(Debug, Deserialize, Clone, PartialEq, JsonSchema, Default)]
+pub struct NovopsBackendsConfig {
+    /// Named Hashicorp Vault configs, such as `{ platform: { address: ... }, team: { address: ... } }`
+    pub hashivault: Option<HashMap<String, HashivaultConfig>>,
+
+    /// Named AWS configs, such as `{ shared: { profile: ... } }`
+    pub aws: Option<HashMap<String, AwsConfig>>
+}
+
+impl NovopsBackendsConfig {
+    /// Merge `other` on top of this config, backends of `other` being merged with backends of the same name
+    pub fn merge(self, other: NovopsBackendsConfig) -> NovopsBackendsConfig {
+        NovopsBackendsConfig {
+            hashivault: merge_config(self.hashivault, other.hashivault, |b, o| merge_named_configs(b, o, HashivaultConfig::merge)),
+            aws: merge_config(self.aws, other.aws, |b, o| merge_named_configs(b, o, AwsConfig::merge)),
+        }
+    }
+}
+
+/// Merge named configs of `other` with configs of the same name in `base`, adding the others
+fn merge_named_configs<T>(mut base: HashMap<String, T>, other: HashMap<String, T>, merge: impl Fn(T, T) -> T) -> HashMap<String, T> {
+    for (name, config) in other {
+        let merged = match base.remove(&name) {
+            Some(b) => merge(b, config),
+            None => config
+        };
+        base.insert(name, merged);
+    }
+    base
+}
+
+/// Keys of a map sorted alphabetically, used for meaningful error messages
+pub fn sorted_keys<V>(map: &HashMap<String, V>) -> Vec<String> {
+    let mut keys: Vec<String> = map.keys().cloned().collect();
+    keys.sort();
+    keys
+}
+
+impl NovopsConfig {
+    /// Apply environment config overrides on top of this config
+    pub fn with_overrides(self, overrides: &NovopsEnvironmentConfig) -> NovopsConfig {
+        NovopsConfig {
+            default: self.default,
+            hashivault: merge_config(self.hashivault, overrides.hashivault.clone(), HashivaultConfig::merge),
+            aws: merge_config(self.aws, overrides.aws.clone(), AwsConfig::merge),
+            backends: merge_config(self.backends, overrides.backends.clone(), NovopsBackendsConfig::merge),
+        }
+    }
+}
+
+/// Module configs overridden for a single environment
+#[derive(Debug, Deserialize, Clone, PartialEq, JsonSchema, Default)]
+pub struct NovopsEnvironmentConfig {
+    /// Hashicorp Vault module config overrides. Fields set here take precedence over global `config.hashivault`.
+    pub hashivault: Option<HashivaultConfig>,
+
+    /// AWS module config overrides. Fields set here take precedence over global `config.aws`.
+    pub aws: Option<AwsConfig>,
+
+    /// Named module config overrides. Backends set here are merged with global `config.backends` of the same name
+    /// (fields set here taking precedence) or added to them.
+    pub backends: Option<NovopsBackendsConfig>
+}
+
+impl NovopsEnvironmentConfig {
+    /// Merge `other` on top of this config, fields set in `other` taking precedence
+    pub fn merge(self, other: NovopsEnvironmentConfig) -> NovopsEnvironmentConfig {
+        NovopsEnvironmentConfig {
+            hashivault: merge_config(self.hashivault, other.hashivault, HashivaultConfig::merge),
+            aws: merge_config(self.aws, other.aws, AwsConfig::merge),
+            backends: merge_config(self.backends, other.backends, NovopsBackendsConfig::merge),
+        }
+    }
+}
+
+/// Merge two optional configs with `merge` if both are set
+fn merge_config<T>(base: Option<T>, other: Option<T>, merge: impl Fn(T, T) -> T) -> Option<T> {
+    match (base, other) {
+        (Some(b), Some(o)) => Some(merge(b, o)),
+        (b, o) => o.or(b)
+    }
 }
 
 #[derive(Debug, Deserialize, Clone, PartialEq, JsonSchema, Default)]
 pub struct NovopsConfigDefault {
     /// Default environment name, selected by default if no user input is provided
     pub environment: Option<String>,
+
+    /// Retry policy for Inputs failing with a transient error (throttling, server or network error).
+    /// Retries are enabled by default with up to 3 attempts. Variables and files may override it with their own `retry`.
+    pub retry: Option<RetryConfig>,
+
+    /// Timeout in seconds for each attempt to resolve an Input. No timeout by default. 
+    /// Variables and files may override it with their own `timeout`.
+    pub timeout: Option<u64>,
 }
 
 /// Modules to be loaded for an environment. Each module defines one or more Input
 /// which will be resolved into Outputs (files & variables)
 #[derive(Debug, Deserialize, Clone, PartialEq, JsonSchema, Default)]
 pub struct NovopsEnvironmentInput {
+
+    /// Environments to inherit Inputs from, such as `[base, aws-common]`. 
+    /// 
+    /// Extended environments are merged in order (later ones taking precedence), 
+    /// then this environment's Inputs are merged on top:
+    /// 
+    /// - `variables` are overridden by `name`
+    /// - `files` are overridden by `variable`, or `symlink` if no `variable` is set (or `dest`, then `name` if neither is set)
+    /// - `sops_dotenv`, `aws_ssm_parameters_by_path`, `expand_json` and `hvault_kv2_all` are appended, skipping identical Inputs
+    /// - `aws`, `hashivault` and `aws_sts_caller_identity` are replaced entirely
+    /// - `aws_credentials_profiles` profiles are overridden by `name`
+    /// - `config` is merged field by field
+    pub extends: Option<Vec<String>>,
+
+    /// Abstract environments can't be loaded directly and are not listed. 
+    /// They're meant to be used as base for other environments via `extends`. 
+    #[serde(rename = "abstract")]
+    pub is_abstract: Option<bool>,
     
     /// Variables resolving to environment variables from provided source
     pub variables: Option<Vec<VariableInput>>,
@@ -75,7 +204,7 @@ pub struct NovopsEnvironmentInput {
     /// Files resolving to concrete files on local filesystem and environment variables pointing to file
     pub files: Option<Vec<FileInput>>,
 
-    /// Assume an AWS Role from local config. 
+    /// Assume an AWS Role from local config or with an OIDC token. 
     /// 
     /// Outputs environment variables `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and `AWS_SESSION_TOKEN` 
     /// with temporary credentials for IAM Role.
@@ -86,6 +215,106 @@ pub struct NovopsEnvironmentInput {
 
     /// Reference SOPS encrypted file(s) as dotenv to load variables
     pub sops_dotenv: Option<Vec<sops::SopsDotenvInput>>,
+
+    /// Load all AWS SSM Parameters under path hierarchies as variables
+    pub aws_ssm_parameters_by_path: Option<Vec<aws::ssm::AwsSSMParametersByPathInput>>,
+
+    /// Expand all keys of JSON object secrets as variables
+    pub expand_json: Option<Vec<json::ExpandJsonInput>>,
+
+    /// Load all keys of Hashicorp Vault Key Value v2 secrets as variables
+    pub hvault_kv2_all: Option<Vec<hashivault::kv2::HashiVaultKeyValueV2AllInput>>,
+
+    /// Load current AWS account ID, caller ARN and region as variables
+    pub aws_sts_caller_identity: Option<aws::caller_identity::AwsStsCallerIdentityInput>,
+
+    /// Output AWS credentials as named profiles in generated AWS credentials and config files
+    pub aws_credentials_profiles: Option<aws::profile::AwsCredentialsProfilesInput>,
+
+    /// Module config overrides for this environment, such as a different Vault address or AWS profile.
+    /// 
+    /// Fields set here take precedence over global `config`. 
+    pub config: Option<NovopsEnvironmentConfig>,
+}
+
+impl NovopsEnvironmentInput {
+
+    /// Merge `other` on top of this environment, `other` Inputs taking precedence.
+    /// `extends` and `abstract` are not merged and are taken from `other`. 
+    pub fn merge(self, other: NovopsEnvironmentInput) -> NovopsEnvironmentInput {
+        NovopsEnvironmentInput {
+            extends: other.extends,
+            is_abstract: other.is_abstract,
+            variables: merge_inputs_by_key(self.variables, other.variables, |v| Some(v.name.clone())),
+            files: merge_inputs_by_key(self.files, other.files, file_merge_key),
+            aws: other.aws.or(self.aws),
+            hashivault: other.hashivault.or(self.hashivault),
+            sops_dotenv: append_inputs(self.sops_dotenv, other.sops_dotenv),
+            aws_ssm_parameters_by_path: append_inputs(self.aws_ssm_parameters_by_path, other.aws_ssm_parameters_by_path),
+            expand_json: append_inputs(self.expand_json, other.expand_json),
+            hvault_kv2_all: append_inputs(self.hvault_kv2_all, other.hvault_kv2_all),
+            aws_sts_caller_identity: other.aws_sts_caller_identity.or(self.aws_sts_caller_identity),
+            aws_credentials_profiles: merge_config(self.aws_credentials_profiles, other.aws_credentials_profiles, |b, o| 
+                aws::profile::AwsCredentialsProfilesInput {
+                    profiles: merge_inputs_by_key(b.profiles, o.profiles, |p| Some(p.name.clone())),
+                    default_profile: o.default_profile.or(b.default_profile)
+                }),
+            config: merge_config(self.config, other.config, NovopsEnvironmentConfig::merge),
+        }
+    }
+}
+
+/// Merge two lists of Inputs, elements of `other` replacing elements of `base` with the same key
+/// (keeping position of replaced element) or being appended otherwise. Elements without key are always appended.
+fn merge_inputs_by_key<T, K: PartialEq>(base: Option<Vec<T>>, other: Option<Vec<T>>, key: impl Fn(&T) -> Option<K>) -> Option<Vec<T>> {
+    match (base, other) {
+        (Some(mut merged), Some(o)) => {
+            for input in o {
+                let input_key = key(&input);
+                match merged.iter().position(|m| input_key.is_some() && key(m) == input_key) {
+                    Some(idx) => merged[idx] = input,
+                    None => merged.push(input)
+                }
+            }
+            Some(merged)
+        },
+        (base, o) => o.or(base)
+    }
+}
+
+/// Append Inputs of `other` to `base`, skipping Inputs already in `base` 
+/// such as Inputs of an environment extended through several parents
+fn append_inputs<T: PartialEq>(base: Option<Vec<T>>, other: Option<Vec<T>>) -> Option<Vec<T>> {
+    match (base, other) {
+        (Some(mut merged), Some(o)) => {
+            for input in o {
+                if !merged.contains(&input) {
+                    merged.push(input);
+                }
+            }
+            Some(merged)
+        },
+        (base, o) => o.or(base)
+    }
+}
+
+/// Files are keyed by variable, symlink, dest or name when merging environments
+type FileMergeKey = (Option<String>, Option<String>, Option<String>, Option<String>);
+
+/// Key identifying a file when merging environments: its variable, symlink, dest or name (first one set).
+/// Files without any of them have no key and are always appended.
+fn file_merge_key(f: &FileInput) -> Option<FileMergeKey> {
+    if f.variable.is_some() {
+        Some((f.variable.clone(), None, None, None))
+    } else if f.symlink.is_some() {
+        Some((None, f.symlink.clone(), None, None))
+    } else if f.dest.is_some() {
+        Some((None, None, f.dest.clone(), None))
+    } else if f.name.is_some() {
+        Some((None, None, None, f.name.clone()))
+    } else {
+        None
+    }
 }
 
 /// Context in which an environment is loaded. Passed to Inputs with ResolveTo() to generate related Output 
@@ -104,11 +333,25 @@ pub struct NovopsContext {
     /// original config loaded at runtime
     pub config_file_data: NovopsConfigFile,
 
+    /// effective module config for current environment: global config with environment overrides applied
+    pub config: NovopsConfig,
+
     /// path to sourceable environment variable file
     pub env_var_filepath: PathBuf,
 
     // enable dry run mode
-    pub dry_run: bool
+    pub dry_run: bool,
+
+    /// variables already resolved for current environment, used by Inputs referencing other variables
+    pub resolved_variables: HashMap<String, String>,
+
+    /// clients shared by Inputs while loading environment, built once per backend
+    #[serde(skip)]
+    pub clients: ClientRegistry,
+
+    /// AWS session credentials provided by environment's `use_for_inputs` Input, used by AWS Inputs instead of local credentials
+    #[serde(skip)]
+    pub aws_session_credentials: Option<Credentials>
 }
 
 /// Trait all Input are implement to generate their final Output value
@@ -131,7 +374,46 @@ pub enum StringResolvableInput {
     GCloudSecretManagerSecretInput(gcloud::secretmanager::GCloudSecretManagerSecretInput),
     AzureKeyvaultSecretInput(azure::vault::AzureKeyvaultSecretInput),
     SopsValueInput(sops::SopsValueInput),
-    AwsS3ObjectInput(aws::s3::AwsS3ObjectInput)
+    AwsS3ObjectInput(aws::s3::AwsS3ObjectInput),
+    AwsEcrAuthorizationTokenInput(aws::ecr::AwsEcrAuthorizationTokenInput),
+    AwsCodeArtifactAuthorizationTokenInput(aws::codeartifact::AwsCodeArtifactAuthorizationTokenInput),
+    AwsEksTokenInput(aws::eks::AwsEksTokenInput),
+    AwsRdsAuthTokenInput(aws::rds::AwsRdsAuthTokenInput),
+    TemplateInput(template::TemplateInput),
+    TransformInput(transform::TransformInput),
+    FirstOfInput(first_of::FirstOfInput)
+}
+
+impl StringResolvableInput {
+    /// Names of other variables this Input references, which must be resolved first
+    pub fn referenced_variables(&self) -> Result<Vec<String>, anyhow::Error> {
+        match self {
+            StringResolvableInput::TemplateInput(t) => t.referenced_variables(),
+            StringResolvableInput::HashiVaultKeyValueV2Input(hv) => hv.referenced_variables(),
+            StringResolvableInput::HashiVaultKeyValueV1Input(hv) => hv.referenced_variables(),
+            StringResolvableInput::AwsSSMParamStoreInput(p) => p.referenced_variables(),
+            StringResolvableInput::AwsSecretsManagerSecretInput(s) => s.referenced_variables(),
+            StringResolvableInput::GCloudSecretManagerSecretInput(s) => s.referenced_variables(),
+            StringResolvableInput::AzureKeyvaultSecretInput(z) => z.referenced_variables(),
+            StringResolvableInput::AwsS3ObjectInput(s) => s.referenced_variables(),
+            StringResolvableInput::TransformInput(t) => t.referenced_variables(),
+            StringResolvableInput::FirstOfInput(f) => f.referenced_variables(),
+            StringResolvableInput::String(_) 
+                | StringResolvableInput::BitwardeItemInput(_) 
+                | StringResolvableInput::SopsValueInput(_)
+                | StringResolvableInput::AwsEcrAuthorizationTokenInput(_)
+                | StringResolvableInput::AwsCodeArtifactAuthorizationTokenInput(_)
+                | StringResolvableInput::AwsEksTokenInput(_)
+                | StringResolvableInput::AwsRdsAuthTokenInput(_) => Ok(vec![])
+        }
+    }
+}
+
+/// Empty string by default
+impl Default for StringResolvableInput {
+    fn default() -> Self {
+        StringResolvableInput::String(String::new())
+    }
 }
 
 
@@ -157,6 +439,13 @@ impl ResolveTo<String> for StringResolvableInput {
             StringResolvableInput::AzureKeyvaultSecretInput(z) => z.resolve(ctx).await,
             StringResolvableInput::SopsValueInput(s) => s.resolve(ctx).await,
             StringResolvableInput::AwsS3ObjectInput(s) => s.resolve(ctx).await,
+            StringResolvableInput::AwsEcrAuthorizationTokenInput(e) => e.resolve(ctx).await,
+            StringResolvableInput::AwsCodeArtifactAuthorizationTokenInput(c) => c.resolve(ctx).await,
+            StringResolvableInput::AwsEksTokenInput(e) => e.resolve(ctx).await,
+            StringResolvableInput::AwsRdsAuthTokenInput(r) => r.resolve(ctx).await,
+            StringResolvableInput::TemplateInput(t) => t.resolve(ctx).await,
+            StringResolvableInput::TransformInput(t) => t.resolve(ctx).await,
+            StringResolvableInput::FirstOfInput(f) => f.resolve(ctx).await,
         }
     }
 }
@@ -169,6 +458,10 @@ impl ResolveTo<String> for StringResolvableInput {
 pub enum BytesResolvableInput {
     AwsSecretsManagerSecretInput(aws::secretsmanager::AwsSecretsManagerSecretInput),
     GCloudSecretManagerSecretInput(gcloud::secretmanager::GCloudSecretManagerSecretInput),
+    AwsS3ObjectInput(aws::s3::AwsS3ObjectInput),
+
+    // before StringResolvableInput so that transformed bytes don't need to be valid UTF-8
+    TransformInput(transform::TransformInput),
     StringResolvableInput(StringResolvableInput),
 
     // skip for schema doc generation as it's useless for human user
@@ -177,6 +470,20 @@ pub enum BytesResolvableInput {
     ByteVec(Vec<u8>)
 }
 
+impl BytesResolvableInput {
+    /// Names of other variables this Input references, which must be resolved first
+    pub fn referenced_variables(&self) -> Result<Vec<String>, anyhow::Error> {
+        match self {
+            BytesResolvableInput::StringResolvableInput(s) => s.referenced_variables(),
+            BytesResolvableInput::AwsSecretsManagerSecretInput(s) => s.referenced_variables(),
+            BytesResolvableInput::GCloudSecretManagerSecretInput(s) => s.referenced_variables(),
+            BytesResolvableInput::AwsS3ObjectInput(s) => s.referenced_variables(),
+            BytesResolvableInput::TransformInput(t) => t.referenced_variables(),
+            BytesResolvableInput::ByteVec(_) => Ok(vec![])
+        }
+    }
+}
+
 #[async_trait]
 impl ResolveTo<Vec<u8>> for BytesResolvableInput {
     async fn resolve(&self, ctx: &NovopsContext) -> Result<Vec<u8>, anyhow::Error> {
@@ -185,6 +492,8 @@ impl ResolveTo<Vec<u8>> for BytesResolvableInput {
             BytesResolvableInput::ByteVec(z) => Ok(z.clone()),
             BytesResolvableInput::AwsSecretsManagerSecretInput(z) => z.resolve(ctx).await,
             BytesResolvableInput::GCloudSecretManagerSecretInput(z) => z.resolve(ctx).await,
+            BytesResolvableInput::AwsS3ObjectInput(z) => z.resolve(ctx).await,
+            BytesResolvableInput::TransformInput(z) => z.resolve(ctx).await,
             BytesResolvableInput::StringResolvableInput(z) => z.resolve(ctx).await.map(|x| x.into_bytes()),
         };
         
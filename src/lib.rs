@@ -1,11 +1,17 @@
 pub mod core;
+pub mod clients;
+pub mod config_file;
 pub mod modules;
 pub mod resolve;
+pub mod retry;
 
 use crate::core::{NovopsEnvironmentInput, NovopsConfigFile, NovopsContext};
+use crate::config_file::read_config_files;
+use crate::clients::ClientRegistry;
 use crate::modules::files::FileOutput;
 use crate::modules::variables::VariableOutput;
-use crate::resolve::resolve_environment_inputs_parallel;
+use crate::resolve::{resolve_environment_inputs_parallel, resolve_aws_credentials_input, declared_fallbacks, InputFallback, DeclaredFallbacks};
+use crate::modules::aws::credential_process::AwsCredentialProcessOutput;
 use log::{info, debug, error, warn};
 use std::os::unix::prelude::{OpenOptionsExt, PermissionsExt};
 use std::os::unix::fs::{MetadataExt, symlink};
@@ -25,7 +31,8 @@ use console::Term;
 
 #[derive(Debug)]
 pub struct NovopsLoadArgs {
-    pub config: Option<String>,
+    /// Config files to load, later files taking precedence. Default config file is used if empty.
+    pub config: Vec<String>,
 
     pub env: Option<String>,
 
@@ -48,7 +55,13 @@ const FORMAT_ALL: [&str; 2] = [ FORMAT_DOTENV_EXPORT, FORMAT_DOTENV_PLAIN ];
 pub struct NovopsOutputs {
     pub context: NovopsContext,
     pub variables: HashMap<String, VariableOutput>,
-    pub files: HashMap<String, FileOutput>
+    pub files: HashMap<String, FileOutput>,
+
+    /// Optional inputs skipped and inputs set to their default as they failed to resolve
+    pub fallbacks: Vec<InputFallback>,
+
+    /// Inputs declared as optional, with a default or using `first_of`
+    pub declared_fallbacks: DeclaredFallbacks
 }
 
 
@@ -91,6 +104,18 @@ pub async fn load_environment_and_exec(args: &NovopsLoadArgs, command_args: Vec<
     Ok(())
 }
 
+/// Used by `novops aws credential-process` to generate AWS credentials from environment's `aws` or `hashivault` Input
+pub async fn resolve_aws_credential_process(args: &NovopsLoadArgs, input: &Option<String>) -> Result<AwsCredentialProcessOutput, anyhow::Error> {
+    init_logger();
+
+    let ctx = make_context(args).await?;
+    let novops_env = get_current_environment(&ctx).await?;
+
+    let creds = resolve_aws_credentials_input(&ctx, novops_env, input).await?;
+
+    AwsCredentialProcessOutput::from_outputs(&creds)
+}
+
 /// Load an environment without side effect and return outputs
 pub async fn load_context_and_resolve(args: &NovopsLoadArgs) -> Result<NovopsOutputs, anyhow::Error> {
     init_logger();
@@ -99,8 +124,9 @@ pub async fn load_context_and_resolve(args: &NovopsLoadArgs) -> Result<NovopsOut
 
     let ctx = make_context(args).await?;
     let novops_env = get_current_environment(&ctx).await?;
+    let declared_fallbacks = declared_fallbacks(&novops_env);
 
-    let (raw_var_outputs, raw_file_outputs) = resolve_environment_inputs_parallel(&ctx, novops_env).await?;
+    let (raw_var_outputs, raw_file_outputs, fallbacks) = resolve_environment_inputs_parallel(&ctx, novops_env).await?;
 
     // Transform raw output into their corresponding HashMaps
     let mut var_outputs: HashMap<String, VariableOutput> = HashMap::new();
@@ -127,7 +153,9 @@ pub async fn load_context_and_resolve(args: &NovopsLoadArgs) -> Result<NovopsOut
     Ok(NovopsOutputs { 
         context: ctx, 
         variables: var_outputs, 
-        files: file_outputs 
+        files: file_outputs,
+        fallbacks,
+        declared_fallbacks
     })
 }
 
@@ -180,12 +208,12 @@ pub fn init_logger() {
 /**
  * List all environments from config file
  */
-pub async fn list_environments(config_file: Option<String>) -> Result<Vec<String>, anyhow::Error> {
+pub async fn list_environments(config_files: Vec<String>) -> Result<Vec<String>, anyhow::Error> {
     init_logger();
 
-    debug!("Listing environments from {:?}", &config_file);
+    debug!("Listing environments from {:?}", &config_files);
 
-    let config = read_config_file(&config_file)
+    let config = read_config_file(&config_files)
         .with_context(|| "Error reading Novops config file")?;
 
     let envs = list_environments_from_config(&config);
@@ -196,13 +224,13 @@ pub async fn list_environments(config_file: Option<String>) -> Result<Vec<String
  * List all outputs for an environment from config file
  * Use dry-run mode to generate outputs
  */
-pub async fn list_outputs_for_environment(config_file: Option<String>, env_name: Option<String>) -> Result<NovopsOutputs, anyhow::Error> {
+pub async fn list_outputs_for_environment(config_files: Vec<String>, env_name: Option<String>) -> Result<NovopsOutputs, anyhow::Error> {
     init_logger();
 
-    debug!("Listing outputs for environment {:?} from {:?}", &env_name, &config_file);
+    debug!("Listing outputs for environment {:?} from {:?}", &env_name, &config_files);
     
     let dryrun_args = NovopsLoadArgs{ 
-        config: config_file,
+        config: config_files,
         env: env_name,
         working_directory: None,
         skip_working_directory_check: Some(false),
@@ -248,13 +276,22 @@ pub async fn make_context(args: &NovopsLoadArgs) -> Result<NovopsContext, anyhow
     // environment variable file which will contain variable output the user can export
     let env_var_filepath = workdir.join("vars");
 
+    // module config with environment overrides applied
+    let novops_env = resolve_environment_extends(&config, &env_name, &mut vec![])?;
+    let effective_config = config.config.clone().unwrap_or_default()
+        .with_overrides(&novops_env.config.unwrap_or_default());
+
     let ctx = NovopsContext {
         env_name: env_name.clone(),
         app_name: app_name.clone(),
         workdir: workdir.clone(),
         config_file_data: config.clone(),
+        config: effective_config,
         env_var_filepath,
-        dry_run: args.dry_run.unwrap_or(false)
+        dry_run: args.dry_run.unwrap_or(false),
+        resolved_variables: HashMap::new(),
+        clients: ClientRegistry::default(),
+        aws_session_credentials: None
     };
 
     debug!("Prepared context: {:?}", &ctx);
@@ -267,28 +304,59 @@ pub async fn make_context(args: &NovopsLoadArgs) -> Result<NovopsContext, anyhow
  * Read config and load all Input types into a NovopsInputs struct
  */
 pub async fn get_current_environment(ctx: &NovopsContext) -> Result<NovopsEnvironmentInput, anyhow::Error> {    
-    let novops_env = ctx.config_file_data.environments.get(&ctx.env_name)
-        .with_context(|| format!("Environment '{}' not found in config.", &ctx.env_name))?;
+    let novops_env = resolve_environment_extends(&ctx.config_file_data, &ctx.env_name, &mut vec![])?;
+
+    if novops_env.is_abstract.unwrap_or(false) {
+        return Err(anyhow::anyhow!("Environment '{}' is abstract and can't be loaded directly. \
+            Abstract environments can only be used as base for other environments with `extends`.", &ctx.env_name));
+    }
     
-    Ok(novops_env.clone())
+    Ok(novops_env)
 }
 
 /**
- * Read Novops configuration file. Use provided config file if Option is Some, default to .novops.y[a]ml in current directory. 
+ * Return environment with all Inputs from extended environments merged, recursively.
+ * `chain` holds environments currently being resolved to detect cycles.
  */
-fn read_config_file(config_path_opt: &Option<String>) -> Result<NovopsConfigFile, anyhow::Error> {
+fn resolve_environment_extends(config: &NovopsConfigFile, env_name: &String, chain: &mut Vec<String>) -> Result<NovopsEnvironmentInput, anyhow::Error> {
+    
+    if chain.contains(env_name) {
+        return Err(anyhow::anyhow!("Environment '{}' extends itself via {} -> {}", env_name, chain.join(" -> "), env_name));
+    }
+
+    let novops_env = match chain.last() {
+        Some(child) => config.environments.get(env_name)
+            .with_context(|| format!("Environment '{}' extended by '{}' not found in config.", env_name, child))?,
+        None => config.environments.get(env_name)
+            .with_context(|| format!("Environment '{}' not found in config.", env_name))?,
+    };
     
-    let current_dir = env::current_dir()?;
-    let cfg_path = get_config_file_path(&current_dir, config_path_opt)?;
+    chain.push(env_name.clone());
+
+    let mut merged = NovopsEnvironmentInput::default();
+    for parent_name in novops_env.extends.clone().unwrap_or_default() {
+        let parent = resolve_environment_extends(config, &parent_name, chain)?;
+        merged = merged.merge(parent);
+    }
+
+    chain.pop();
+
+    Ok(merged.merge(novops_env.clone()))
+}
 
-    debug!("Loading config file path '{:?}'", &cfg_path);
+/**
+ * Read Novops configuration files and their includes, merged in order. Default to .novops.y[a]ml in current directory if no config file provided. 
+ */
+fn read_config_file(config_paths: &[String]) -> Result<NovopsConfigFile, anyhow::Error> {
     
-    let f = std::fs::File::open(&cfg_path)
-        .with_context(|| format!("Failed to open Novops config {:?}", &cfg_path))?;  
-    let config: NovopsConfigFile = serde_yaml::from_reader(f)
-        .with_context(|| format!("Error parsing config file {:?}. Does it match expected config schema?", &cfg_path))?;
+    let cfg_paths = if config_paths.is_empty() {
+        let current_dir = env::current_dir()?;
+        vec![get_config_file_path(&current_dir, &None)?]
+    } else {
+        config_paths.iter().map(PathBuf::from).collect()
+    };
 
-    Ok(config)
+    read_config_files(&cfg_paths)
 }
 
 /**
@@ -435,9 +503,7 @@ pub fn check_working_dir_permissions(workdir: &PathBuf) -> Result<(), anyhow::Er
  */
 fn prompt_for_environment(config_file_data: &NovopsConfigFile) -> Result<String, anyhow::Error>{
 
-    let environments = config_file_data.environments.iter()
-        .map(|e| e.0.clone())
-        .collect::<Vec<String>>();
+    let environments = list_environments_from_config(config_file_data);
 
     let default = config_file_data.config.clone()
         .and_then(|c| c.default)
@@ -486,10 +552,13 @@ fn prompt_for_environment(config_file_data: &NovopsConfigFile) -> Result<String,
 }
 
 /**
- * Return a sorted list of environments from config
+ * Return a sorted list of environments from config, excluding abstract environments
  */
 fn list_environments_from_config(config_file_data: &NovopsConfigFile) -> Vec<String> {
-    let mut sorted = config_file_data.environments.keys().cloned().collect::<Vec<String>>();
+    let mut sorted = config_file_data.environments.iter()
+        .filter(|(_, e)| !e.is_abstract.unwrap_or(false))
+        .map(|(name, _)| name.clone())
+        .collect::<Vec<String>>();
     sorted.sort();
     sorted
 }
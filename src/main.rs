@@ -4,6 +4,7 @@ use anyhow::{Context, anyhow};
 
 use clap::{Arg, Command, value_parser, ArgAction, crate_version, ArgMatches};
 use novops::{self, init_logger, get_config_schema, NovopsLoadArgs};
+use novops::resolve::FallbackOutcome;
 use clap_complete::{generate, Shell};
 use std::collections::HashMap;
 
@@ -19,8 +20,9 @@ fn build_cli() -> Command {
         .long("config")
         .env("NOVOPS_CONFIG")
         .value_name("FILE")
-        .help("Configuration to use.")
+        .help("Configuration to use. Can be repeated to merge several configs, later ones taking precedence.")
         .required(false)
+        .action(ArgAction::Append)
         .num_args(1);
         
     let arg_environment = Arg::new("environment")
@@ -139,6 +141,33 @@ fn build_cli() -> Command {
                     .arg(&arg_output_format)
                 )
         )
+        .subcommand(
+            Command::new("aws")
+                .about("AWS helpers")
+                .subcommand(
+                    Command::new("credential-process")
+                    .about("Output AWS credentials from environment for AWS credential_process.")
+                    .long_about("Output AWS credentials from environment's `aws` or `hashivault` Input \
+                        as JSON document expected by AWS credential_process setting. Example in ~/.aws/config: \n\
+                        \n\
+                        [profile dev]\n\
+                        credential_process = novops aws credential-process -c /path/to/.novops.yml -e dev\n\
+                        "
+                    )
+                    .arg(&arg_config)
+                    .arg(&arg_environment)
+                    .arg(&arg_workdir)
+                    .arg(&arg_dryrun)
+                    .arg(&arg_skip_workdir_check)
+                    .arg(Arg::new("input")
+                        .help("Input generating credentials. Default to `aws` if set in environment, `hashivault` otherwise.")
+                        .long("input")
+                        .value_name("INPUT")
+                        .value_parser(["aws", "hashivault"])
+                        .required(false)
+                    )
+                )
+        )
         .subcommand(
             Command::new("completion")
             .about("Output completion code for various shells.")
@@ -181,6 +210,12 @@ async fn main() -> Result<(), anyhow::Error> {
                 _ => cmd_error().await,
             }
         }
+        Some(("aws", aws_subc)) => {
+            match aws_subc.subcommand() {
+                Some(("credential-process", credential_process_subc)) => cmd_aws_credential_process(credential_process_subc).await,
+                _ => cmd_error().await,
+            }
+        }
         Some(("completion", cmd)) => cmd_completion(cmd, app).await,
         Some(("schema", schema_args)) => cmd_schema(schema_args).await,
         _ => cmd_error().await,
@@ -197,12 +232,12 @@ async fn main() -> Result<(), anyhow::Error> {
 //
 
 async fn cmd_list_envs(cmd_args: &ArgMatches) -> Result<(), anyhow::Error> {
-    let config_file = cmd_args.get_one::<String>("config").cloned();
+    let config_files = get_config_files_arg(cmd_args);
 
     let output_format = cmd_args.get_one::<String>("format")
         .ok_or(anyhow!("Format is None. This is probably a bug as CLI defines default value."))?.clone();
 
-    let envs = novops::list_environments(config_file).await
+    let envs = novops::list_environments(config_files).await
         .with_context(|| "Failed to list environments.")?;
 
     match output_format.as_str() {
@@ -224,18 +259,20 @@ async fn cmd_list_envs(cmd_args: &ArgMatches) -> Result<(), anyhow::Error> {
 
 async fn cmd_list_outputs(cmd_args: &ArgMatches) -> Result<(), anyhow::Error> {
 
-    let config_file = cmd_args.get_one::<String>("config").cloned();
+    let config_files = get_config_files_arg(cmd_args);
 
     let env_name = cmd_args.get_one::<String>("environment").map(String::from);
 
     let output_format = cmd_args.get_one::<String>("format")
         .ok_or(anyhow!("Format is None. This is probably a bug as CLI defines default value."))?.clone();
 
-    let outputs = novops::list_outputs_for_environment(config_file, env_name).await
+    let outputs = novops::list_outputs_for_environment(config_files, env_name).await
         .with_context(|| "Failed to list outputs.")?;
     
     
-    // Result in the form { "variables": [ "VAR_NAME1", "VAR_NAME2" ], "files": ["/path/to/file1", "/path/to/file2", ...] }
+    // Result in the form { "variables": [ "VAR_NAME1", "VAR_NAME2" ], "files": ["/path/to/file1", "/path/to/file2", ...],
+    //  "optional": ["OPTIONAL_VAR", ...], "defaults": ["VAR_WITH_DEFAULT", ...], "first_of": ["VAR_WITH_FIRST_OF", ...] }
+    // Listing is a dry-run: optional, defaults and first_of are inputs declared with a fallback behavior
     let mut result: HashMap<String, Vec<String>> = HashMap::new();
     result.insert("variables".to_string(), 
         outputs.variables.iter().map(|e| e.1.name.clone()).collect::<Vec<String>>()
@@ -243,6 +280,9 @@ async fn cmd_list_outputs(cmd_args: &ArgMatches) -> Result<(), anyhow::Error> {
     result.insert("files".to_string(), 
         outputs.files.iter().map(|f| f.0.clone()).collect::<Vec<String>>()
     );
+    result.insert("optional".to_string(), outputs.declared_fallbacks.optional.clone());
+    result.insert("defaults".to_string(), outputs.declared_fallbacks.defaults.clone());
+    result.insert("first_of".to_string(), outputs.declared_fallbacks.first_of.clone());
 
     match output_format.as_str() {
         LIST_CMD_OUTPUT_JSON => {
@@ -258,6 +298,36 @@ async fn cmd_list_outputs(cmd_args: &ArgMatches) -> Result<(), anyhow::Error> {
             println!("Files:");
             println!();
             println!("{}", result.get("files").unwrap().join("\n"));
+
+            // only show declared fallbacks if any
+            let declared = [
+                ("optional", "Optional (not generated if they fail to resolve):"),
+                ("defaults", "With default (default used if they fail to resolve):"),
+                ("first_of", "First of (first source resolving successfully is used):"),
+            ];
+            for (key, title) in declared {
+                let names = result.get(key).unwrap();
+                if !names.is_empty() {
+                    println!();
+                    println!("{}", title);
+                    println!();
+                    println!("{}", names.join("\n"));
+                }
+            }
+
+            // dry-run may still fail to resolve some inputs, such as templates referencing unset environment variables
+            if !outputs.fallbacks.is_empty() {
+                println!();
+                println!("Failed to resolve (skipped or using default):");
+                println!();
+                for f in &outputs.fallbacks {
+                    let outcome = match f.outcome {
+                        FallbackOutcome::Skipped => "skipped",
+                        FallbackOutcome::DefaultUsed => "default",
+                    };
+                    println!("{} ({}): {}", f.input, outcome, f.reason);
+                }
+            }
         },
         _ => {
             return Err(anyhow!("Unknown output format: {}", output_format));
@@ -305,6 +375,21 @@ async fn cmd_load(cmd_args: &ArgMatches) -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+async fn cmd_aws_credential_process(cmd_args: &ArgMatches) -> Result<(), anyhow::Error> {
+
+    let input = cmd_args.get_one::<String>("input").map(String::from);
+    let novops_load_args = build_novops_args(cmd_args)?;
+
+    let creds = novops::resolve_aws_credential_process(&novops_load_args, &input).await
+        .with_context(|| "Failed to generate AWS credentials.")?;
+
+    let json = serde_json::to_string(&creds)
+        .with_context(|| "Failed to serialize AWS credentials to JSON.")?;
+    println!("{}", json);
+
+    Ok(())
+}
+
 async fn cmd_completion(cmd_args: &ArgMatches, mut app: Command) -> Result<(), anyhow::Error> {
     let name = app.get_name().to_string();
     let shell_arg = cmd_args.get_one::<Shell>("shell")
@@ -335,7 +420,7 @@ async fn cmd_error() -> Result<(), anyhow::Error> {
  */
 fn build_novops_args(cmd_args: &ArgMatches) -> Result<NovopsLoadArgs, anyhow::Error> {
     let args = novops::NovopsLoadArgs{ 
-        config: cmd_args.get_one::<String>("config").cloned(),
+        config: get_config_files_arg(cmd_args),
         env: cmd_args.get_one::<String>("environment").map(String::from),
         working_directory: cmd_args.get_one::<String>("working_dir").map(String::from),
         skip_working_directory_check: cmd_args.get_one::<bool>("skip_workdir_check").copied(),
@@ -343,4 +428,13 @@ fn build_novops_args(cmd_args: &ArgMatches) -> Result<NovopsLoadArgs, anyhow::Er
     };
 
     Ok(args)
-}
\ No newline at end of file
+}
+
+/**
+ * Config files passed with -c/--config, possibly repeated
+ */
+fn get_config_files_arg(cmd_args: &ArgMatches) -> Vec<String> {
+    cmd_args.get_many::<String>("config")
+        .map(|c| c.cloned().collect())
+        .unwrap_or_default()
+}
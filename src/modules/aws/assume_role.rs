@@ -1,15 +1,20 @@
+use std::collections::HashMap;
+use std::time::{Duration, UNIX_EPOCH};
 use serde::Deserialize;
 use async_trait::async_trait;
 use anyhow::{self, Context};
 use rand::{distributions::Alphanumeric, Rng};
 use schemars::JsonSchema;
 use log::warn;
+use console::Term;
 
-use crate::core::{ResolveTo, NovopsContext};
+use crate::core::{ResolveTo, NovopsContext, StringResolvableInput};
 use crate::modules::variables::VariableOutput;
-use crate::modules::aws::client::get_client_with_profile;
+use crate::modules::aws::client::{get_client, get_client_with_profile, build_client_with_credentials, AssumeRoleParams};
+use crate::clients::SharedAwsClient;
+use aws_sdk_sts::types::Credentials;
 
-const STS_ROLE_SESSION_NAME_MAX_LENGTH: usize = 64;
+pub const STS_ROLE_SESSION_NAME_MAX_LENGTH: usize = 64;
 
 /// Assume an IAM Role 
 #[derive(Debug, Deserialize, Clone, PartialEq, JsonSchema)]
@@ -24,54 +29,248 @@ pub struct AwsAssumeRoleInput {
     /// Duration of the role session (seconds). 
     /// Can range from 900 seconds up to the maximum session duration set for the role.
     /// Default to 1h (3600).
-    pub duration_seconds: Option<i32>
+    pub duration_seconds: Option<i32>,
+
+    /// Role session name, used for all roles in chain. Can be a plain string or any Input resolving to a string, 
+    /// such as `template` using `${env:NAME}` references (other variables can't be referenced).
+    /// 
+    /// Default to `novops-<app>-<environment>-<random suffix>`. 
+    pub role_session_name: Option<StringResolvableInput>,
+
+    /// Serial number or ARN of MFA device, such as `arn:aws:iam::123456789012:mfa/user`. 
+    /// Required if role trust policy requires MFA.
+    pub mfa_serial: Option<String>,
+
+    /// MFA token code when using `mfa_serial`. Can be a plain string or any Input resolving to a string. 
+    /// If not set, token code is prompted on stderr. 
+    pub mfa_token_code: Option<StringResolvableInput>,
+
+    #[serde(flatten)]
+    pub session: AwsRoleSessionOptions,
+
+    /// Resolve this role first and use its credentials for all other AWS Inputs of environment 
+    /// (SSM parameters, Secrets Manager secrets, S3 objects...) instead of local credentials. Default: false.
+    /// 
+    /// Useful to load secrets from another AWS account.
+    pub use_for_inputs: Option<bool>,
+
+    /// Output credentials as this named profile in a generated AWS credentials file instead of `AWS_ACCESS_KEY_ID`, 
+    /// `AWS_SECRET_ACCESS_KEY` and `AWS_SESSION_TOKEN` variables. `AWS_SHARED_CREDENTIALS_FILE`, `AWS_CONFIG_FILE` 
+    /// and `AWS_PROFILE` variables are output instead.
+    pub credentials_profile: Option<String>,
+
+    /// Roles to assume in order after `role_arn`, each using credentials of previous role (role chaining). 
+    /// Final role credentials are output. 
+    /// 
+    /// Note that AWS limits role chaining sessions to 1h: `duration_seconds` must not exceed 3600.
+    pub chain: Option<Vec<AwsAssumeRoleChainLink>>,
+
+    /// Named AWS backend from `config.backends.aws` to assume role(s) with. Default to `config.aws`.
+    pub backend: Option<String>
+}
+
+/// Role assumed in a role chain with credentials of previous role
+#[derive(Debug, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct AwsAssumeRoleChainLink {
+
+    /// Full IAM Role ARN
+    pub role_arn: String,
+
+    #[serde(flatten)]
+    pub session: AwsRoleSessionOptions
+}
+
+/// Options of an assumed role session
+#[derive(Debug, Deserialize, Clone, PartialEq, JsonSchema, Default)]
+pub struct AwsRoleSessionOptions {
+
+    /// External ID required by role trust policy, usually for third-party account access
+    pub external_id: Option<String>,
+
+    /// Session tags as key/value pairs
+    pub tags: Option<HashMap<String, String>>,
+
+    /// Session tag keys passed to following roles in chain
+    pub transitive_tag_keys: Option<Vec<String>>,
+
+    /// Source identity set on role session, kept by following roles in chain
+    pub source_identity: Option<String>,
+
+    /// Inline session policy as JSON, further restricting role permissions
+    pub policy: Option<String>,
+
+    /// Managed policy ARNs used as session policies, further restricting role permissions
+    pub policy_arns: Option<Vec<String>>
+}
+
+impl AwsRoleSessionOptions {
+    fn params(&self, role_arn: &str, session_name: &str, duration_seconds: i32) -> AssumeRoleParams {
+        let mut tags: Vec<(String, String)> = self.tags.clone().unwrap_or_default().into_iter().collect();
+        tags.sort();
+
+        AssumeRoleParams {
+            role_arn: role_arn.to_string(),
+            session_name: session_name.to_string(),
+            duration_seconds,
+            external_id: self.external_id.clone(),
+            tags,
+            transitive_tag_keys: self.transitive_tag_keys.clone().unwrap_or_default(),
+            source_identity: self.source_identity.clone(),
+            policy: self.policy.clone(),
+            policy_arns: self.policy_arns.clone().unwrap_or_default(),
+            serial_number: None,
+            token_code: None
+        }
+    }
+}
+
+impl AwsAssumeRoleInput {
+
+    /// MFA token code from config, or prompted to user
+    async fn mfa_token_code(&self, ctx: &NovopsContext, mfa_serial: &str) -> Result<String, anyhow::Error> {
+        match &self.mfa_token_code {
+            Some(code) => code.resolve(ctx).await
+                .with_context(|| format!("Couldn't resolve MFA token code for {}", mfa_serial)),
+            None if ctx.dry_run => Ok(String::from("123456")),
+            None => prompt_mfa_token_code(mfa_serial).await
+        }
+    }
+
+    /// Copy of this Input with MFA token code resolved (or prompted) once, 
+    /// so that user is not prompted again if resolving this Input is retried
+    pub async fn with_resolved_mfa_token_code(&self, ctx: &NovopsContext) -> Result<AwsAssumeRoleInput, anyhow::Error> {
+        match &self.mfa_serial {
+            Some(mfa_serial) => {
+                let code = self.mfa_token_code(ctx, mfa_serial).await?;
+                Ok(AwsAssumeRoleInput { mfa_token_code: Some(StringResolvableInput::String(code)), ..self.clone() })
+            },
+            None => Ok(self.clone())
+        }
+    }
 }
 
 #[async_trait]
 impl ResolveTo<Vec<VariableOutput>> for AwsAssumeRoleInput {
     async fn resolve(&self, ctx: &NovopsContext) -> Result<Vec<VariableOutput>, anyhow::Error> {
         
-        let client = get_client_with_profile(ctx, &self.source_profile).await;
-
-        let session_random_suffix: String = rand::thread_rng()
-            .sample_iter(&Alphanumeric)
-            .take(7)
-            .collect();
+        // without source profile, use environment session credentials if any (such as Hashivault AWS credentials)
+        let client = match &self.source_profile {
+            Some(_) => get_client_with_profile(ctx, &self.backend, &self.source_profile).await?,
+            None => get_client(ctx, &self.backend).await?
+        };
 
-        let mut role_session_name = format!("novops-{:}-{:}-{:}", &ctx.app_name, &ctx.env_name, &session_random_suffix);
+        let role_session_name = resolve_role_session_name(ctx, &self.role_arn, &self.role_session_name).await?;
+        let duration_seconds = self.duration_seconds.unwrap_or(3600);
 
-        // session name is max 64 characters length
-        // by default use full app and env name with random suffix
-        // truncate if name > 64 chars to avoid error but print warning
-        // see https://docs.aws.amazon.com/STS/latest/APIReference/API_AssumeRole.html        
-        if role_session_name.len() > STS_ROLE_SESSION_NAME_MAX_LENGTH {
-           let original_role_session_name = role_session_name.clone();
+        let mut params = self.session.params(&self.role_arn, &role_session_name, duration_seconds);
 
-           // when truncating, truncate based on app and env name but keep random identifier
-           let mut truncated_rsname = format!("novops-{:}-{:}", &ctx.app_name, &ctx.env_name);
-           truncated_rsname.truncate(STS_ROLE_SESSION_NAME_MAX_LENGTH-session_random_suffix.len()-1);
+        if let Some(mfa_serial) = &self.mfa_serial {
+            params.serial_number = Some(mfa_serial.clone());
+            params.token_code = Some(self.mfa_token_code(ctx, mfa_serial).await?);
+        }
 
-           role_session_name = format!("{:}-{:}", truncated_rsname, &session_random_suffix);
+        let mut creds = assume_role(&client, &params).await?;
 
-           warn!("WARNING: Role session name {:} truncated to {:} as length > 64 characters. \
-           Consider using shorter application or environment name to avoid losing information with truncation.", 
-           &original_role_session_name, &role_session_name);
+        // each role in chain is assumed with previous role credentials
+        for link in self.chain.clone().unwrap_or_default() {
+            let chain_client = build_client_with_credentials(ctx, &self.backend, sdk_credentials(&creds)).await?;
+            creds = assume_role(&chain_client, &link.session.params(&link.role_arn, &role_session_name, duration_seconds)).await
+                .with_context(|| format!("Couldn't assume role {} in role chain", &link.role_arn))?;
         }
 
-        let duration_seconds = self.duration_seconds.unwrap_or(3600);
+        Ok(credentials_outputs(&creds))
+    }
+}
+
+/// Session name from config, or generated from app and environment names
+pub async fn resolve_role_session_name(ctx: &NovopsContext, role_arn: &str, role_session_name: &Option<StringResolvableInput>) 
+        -> Result<String, anyhow::Error> {
+    match role_session_name {
+        Some(name) => {
+            let name = name.resolve(ctx).await
+                .with_context(|| format!("Couldn't resolve role session name for role {}", role_arn))?;
+
+            if name.len() > STS_ROLE_SESSION_NAME_MAX_LENGTH {
+                return Err(anyhow::anyhow!("Role session name '{}' is longer than {} characters", name, STS_ROLE_SESSION_NAME_MAX_LENGTH));
+            }
+
+            Ok(name)
+        },
+        None => Ok(generate_role_session_name(ctx))
+    }
+}
+
+/**
+ * Generate session name from app and environment names with a random suffix
+ * 
+ * Session name is max 64 characters length: truncate if longer to avoid error but print warning
+ * See https://docs.aws.amazon.com/STS/latest/APIReference/API_AssumeRole.html
+ */
+fn generate_role_session_name(ctx: &NovopsContext) -> String {
+    let session_random_suffix: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(7)
+        .collect();
+
+    let mut role_session_name = format!("novops-{:}-{:}-{:}", &ctx.app_name, &ctx.env_name, &session_random_suffix);
 
-        let assumed_role = client.assume_role(&self.role_arn, &role_session_name, duration_seconds).await?;
-    
-        let creds = &assumed_role.credentials.clone()
-            .with_context(|| format!("Can't assume role: returned Credentials Option was None for {:?}", &assumed_role))?;
-
-        return Ok(
-            vec![
-                VariableOutput{name: "AWS_ACCESS_KEY_ID".into(), value: creds.access_key_id.clone()},
-                VariableOutput{name: "AWS_SECRET_ACCESS_KEY".into(), value: creds.secret_access_key.clone()},
-                VariableOutput{name: "AWS_SESSION_TOKEN".into(), value: creds.session_token.clone()} ,
-                VariableOutput{name: "AWS_SESSION_EXPIRATION".into(), value: creds.expiration.clone().secs().to_string() } 
-            ]
-        )
+    if role_session_name.len() > STS_ROLE_SESSION_NAME_MAX_LENGTH {
+       let original_role_session_name = role_session_name.clone();
+
+       // when truncating, truncate based on app and env name but keep random identifier
+       let mut truncated_rsname = format!("novops-{:}-{:}", &ctx.app_name, &ctx.env_name);
+       truncated_rsname.truncate(STS_ROLE_SESSION_NAME_MAX_LENGTH-session_random_suffix.len()-1);
+
+       role_session_name = format!("{:}-{:}", truncated_rsname, &session_random_suffix);
+
+       warn!("WARNING: Role session name {:} truncated to {:} as length > 64 characters. \
+       Consider using shorter application or environment name to avoid losing information with truncation.", 
+       &original_role_session_name, &role_session_name);
     }
+
+    role_session_name
+}
+
+/// Prompt user for MFA token code on stderr, as stdout may be used for outputs
+async fn prompt_mfa_token_code(mfa_serial: &str) -> Result<String, anyhow::Error> {
+    let prompt = format!("Enter MFA code for {}", mfa_serial);
+
+    let code: String = tokio::task::spawn_blocking(move || {
+        dialoguer::Input::<String>::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt(prompt)
+            .interact_text_on(&Term::stderr())
+    }).await?
+        .with_context(|| format!("Couldn't prompt for MFA token code for {}", mfa_serial))?;
+
+    Ok(code.trim().to_string())
+}
+
+async fn assume_role(client: &SharedAwsClient, params: &AssumeRoleParams) -> Result<Credentials, anyhow::Error> {
+    let assumed_role = client.assume_role(params).await?;
+
+    assumed_role.credentials.clone()
+        .with_context(|| format!("Can't assume role: returned Credentials Option was None for {:?}", &assumed_role))
+}
+
+/// Variables output for STS credentials, used natively by AWS CLI and SDKs
+pub fn credentials_outputs(creds: &Credentials) -> Vec<VariableOutput> {
+    vec![
+        VariableOutput{name: "AWS_ACCESS_KEY_ID".into(), value: creds.access_key_id.clone()},
+        VariableOutput{name: "AWS_SECRET_ACCESS_KEY".into(), value: creds.secret_access_key.clone()},
+        VariableOutput{name: "AWS_SESSION_TOKEN".into(), value: creds.session_token.clone()} ,
+        VariableOutput{name: "AWS_SESSION_EXPIRATION".into(), value: creds.expiration.clone().secs().to_string() } 
+    ]
+}
+
+/// Convert STS credentials to SDK credentials usable by a client
+fn sdk_credentials(creds: &Credentials) -> aws_sdk_sts::config::Credentials {
+    let expiration = UNIX_EPOCH + Duration::from_secs(creds.expiration.secs().max(0) as u64);
+
+    aws_sdk_sts::config::Credentials::new(
+        creds.access_key_id.clone(), 
+        creds.secret_access_key.clone(), 
+        Some(creds.session_token.clone()), 
+        Some(expiration), 
+        "novops-role-chain"
+    )
 }
\ No newline at end of file
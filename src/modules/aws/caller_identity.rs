@@ -0,0 +1,45 @@
+use serde::Deserialize;
+use async_trait::async_trait;
+use anyhow;
+use schemars::JsonSchema;
+use crate::core::{ResolveTo, NovopsContext};
+use crate::modules::aws::client::get_client;
+use crate::modules::variables::VariableOutput;
+
+/// Load current AWS account ID, caller ARN and region as variables, such as to build resource names
+///
+/// Uses same credentials as other AWS Inputs: identity of environment assumed role requires `aws.assume_role.use_for_inputs`.
+#[derive(Debug, Deserialize, Clone, PartialEq, JsonSchema, Default)]
+pub struct AwsStsCallerIdentityInput {
+
+    /// Variable name for account ID. Default: `AWS_ACCOUNT_ID`
+    pub account_id_variable: Option<String>,
+
+    /// Variable name for caller ARN. Default: `AWS_CALLER_ARN`
+    pub arn_variable: Option<String>,
+
+    /// Variable name for region used by AWS client. Default: `AWS_REGION`
+    pub region_variable: Option<String>,
+
+    /// Named AWS backend from `config.backends.aws` to use. Default to `config.aws`.
+    pub backend: Option<String>
+}
+
+#[async_trait]
+impl ResolveTo<Vec<VariableOutput>> for AwsStsCallerIdentityInput {
+    async fn resolve(&self, ctx: &NovopsContext) -> Result<Vec<VariableOutput>, anyhow::Error> {
+        let client = get_client(ctx, &self.backend).await?;
+        let identity = client.get_caller_identity().await?;
+
+        let variable = |name: &Option<String>, default: &str, value: String| VariableOutput {
+            name: name.clone().unwrap_or(default.to_string()),
+            value
+        };
+
+        Ok(vec![
+            variable(&self.account_id_variable, "AWS_ACCOUNT_ID", identity.account_id),
+            variable(&self.arn_variable, "AWS_CALLER_ARN", identity.arn),
+            variable(&self.region_variable, "AWS_REGION", identity.region),
+        ])
+    }
+}
@@ -0,0 +1,47 @@
+use serde::Deserialize;
+use async_trait::async_trait;
+use anyhow;
+use schemars::JsonSchema;
+use crate::core::{ResolveTo, NovopsContext};
+use crate::modules::aws::client::get_client;
+
+/// Generate a CodeArtifact authorization token for package managers (npm, pip, Maven...)
+#[derive(Debug, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct AwsCodeArtifactAuthorizationTokenInput {
+
+    pub aws_codeartifact_authorization_token: AwsCodeArtifactAuthorizationToken
+}
+
+/// Generate a CodeArtifact authorization token
+/// 
+/// Maps to GetAuthorizationToken API. See https://docs.aws.amazon.com/codeartifact/latest/APIReference/API_GetAuthorizationToken.html
+#[derive(Debug, Deserialize, Clone, PartialEq, JsonSchema, Default)]
+pub struct AwsCodeArtifactAuthorizationToken {
+
+    /// CodeArtifact domain name
+    pub domain: String,
+
+    /// Account ID owning domain. Default to current account.
+    pub domain_owner: Option<String>,
+
+    /// Token validity in seconds, from 900 to 43200, or 0 to use role session duration. Default: 43200 (12h)
+    pub duration_seconds: Option<i64>,
+
+    /// Named AWS backend from `config.backends.aws` to use. Default to `config.aws`.
+    pub backend: Option<String>
+}
+
+#[async_trait]
+impl ResolveTo<String> for AwsCodeArtifactAuthorizationTokenInput {
+    async fn resolve(&self, ctx: &NovopsContext) -> Result<String, anyhow::Error> {
+        let token = &self.aws_codeartifact_authorization_token;
+
+        let client = get_client(ctx, &token.backend).await?;
+
+        let output = client.get_codeartifact_authorization_token(&token.domain, &token.domain_owner, token.duration_seconds).await?;
+
+        Ok(output.authorization_token()
+            .ok_or(anyhow::anyhow!("No authorization token in CodeArtifact response for domain {}", &token.domain))?
+            .to_string())
+    }
+}
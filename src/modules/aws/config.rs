@@ -1,11 +1,72 @@
+use crate::core::{ResolveTo, NovopsContext};
 use crate::modules::aws::assume_role::AwsAssumeRoleInput;
+use crate::modules::aws::web_identity::AwsAssumeRoleWithWebIdentityInput;
+use crate::modules::variables::VariableOutput;
 
+use aws_sdk_sts::config::Credentials;
+use async_trait::async_trait;
 use schemars::JsonSchema;
 use serde::Deserialize;
 
+/// AWS credentials to generate. Exactly one of `assume_role` or `assume_role_with_web_identity` must be set.
 #[derive(Debug, Deserialize, Clone, PartialEq, JsonSchema)]
 pub struct AwsInput {
-    pub assume_role: AwsAssumeRoleInput
+    pub assume_role: Option<AwsAssumeRoleInput>,
+
+    /// Assume role with an OpenID Connect (OIDC) token, such as a CI job token
+    pub assume_role_with_web_identity: Option<AwsAssumeRoleWithWebIdentityInput>
+}
+
+impl AwsInput {
+    /// Whether generated credentials must be used by other AWS Inputs
+    pub fn use_for_inputs(&self) -> bool {
+        let assume_role = self.assume_role.as_ref()
+            .is_some_and(|a| a.use_for_inputs.unwrap_or(false));
+        let web_identity = self.assume_role_with_web_identity.as_ref()
+            .is_some_and(|w| w.use_for_inputs.unwrap_or(false));
+
+        assume_role || web_identity
+    }
+
+    /// Copy of this Input with `assume_role` MFA token code resolved, prompting user if needed. 
+    /// Prompts must be done once before resolving this Input with retries.
+    pub async fn with_resolved_mfa_token_code(&self, ctx: &NovopsContext) -> Result<AwsInput, anyhow::Error> {
+        let assume_role = match &self.assume_role {
+            Some(a) => Some(a.with_resolved_mfa_token_code(ctx).await?),
+            None => None
+        };
+
+        Ok(AwsInput { assume_role, ..self.clone() })
+    }
+
+    /// Named AWS backend used to generate credentials, if any
+    pub fn backend(&self) -> Option<String> {
+        match (&self.assume_role, &self.assume_role_with_web_identity) {
+            (Some(a), _) => a.backend.clone(),
+            (None, Some(w)) => w.backend.clone(),
+            (None, None) => None
+        }
+    }
+
+    /// Profile to output credentials as, if any
+    pub fn credentials_profile(&self) -> Option<String> {
+        match (&self.assume_role, &self.assume_role_with_web_identity) {
+            (Some(a), _) => a.credentials_profile.clone(),
+            (None, Some(w)) => w.credentials_profile.clone(),
+            (None, None) => None
+        }
+    }
+}
+
+#[async_trait]
+impl ResolveTo<Vec<VariableOutput>> for AwsInput {
+    async fn resolve(&self, ctx: &NovopsContext) -> Result<Vec<VariableOutput>, anyhow::Error> {
+        match (&self.assume_role, &self.assume_role_with_web_identity) {
+            (Some(a), None) => a.resolve(ctx).await,
+            (None, Some(w)) => w.resolve(ctx).await,
+            _ => Err(anyhow::anyhow!("Exactly one of `aws.assume_role` or `aws.assume_role_with_web_identity` must be set"))
+        }
+    }
 }
 
 
@@ -19,6 +80,12 @@ pub struct AwsClientConfig {
     pub endpoint: Option<String>,
     pub region: Option<String>,
     pub identity_cache: Option<IdentityCache>,
+
+    /// IAM Identity Center (SSO) configuration to derive role credentials from
+    pub sso: Option<AwsSsoConfig>,
+
+    /// Static credentials overriding credentials from environment and profile
+    pub credentials: Option<Credentials>,
 }
 
 impl From<&AwsConfig> for AwsClientConfig {
@@ -27,7 +94,9 @@ impl From<&AwsConfig> for AwsClientConfig {
             profile: cf.profile.clone(),
             endpoint: cf.endpoint.clone(),
             region: cf.region.clone(),
-            identity_cache: cf.identity_cache.clone()
+            identity_cache: cf.identity_cache.clone(),
+            sso: cf.sso.clone(),
+            credentials: None
         }
     }
 }
@@ -42,6 +111,11 @@ impl AwsClientConfig {
         self.endpoint = Some(endpoint.to_string());
         self
     }
+
+    pub fn credentials(&mut self, credentials: Credentials) -> &mut AwsClientConfig {
+        self.credentials = Some(credentials);
+        self
+    }
 }
 
 
@@ -63,7 +137,24 @@ pub struct AwsConfig {
     pub region: Option<String>,
 
     /// AWS SDK identity cache configuration
-    pub identity_cache: Option<IdentityCache>
+    pub identity_cache: Option<IdentityCache>,
+
+    /// Use IAM Identity Center (SSO) role credentials. If no valid SSO token is cached locally,
+    /// Novops prompts on stderr to authorize access with device authorization flow.
+    pub sso: Option<AwsSsoConfig>
+}
+
+impl AwsConfig {
+    /// Merge `other` on top of this config, fields set in `other` taking precedence
+    pub fn merge(self, other: AwsConfig) -> AwsConfig {
+        AwsConfig {
+            endpoint: other.endpoint.or(self.endpoint),
+            profile: other.profile.or(self.profile),
+            region: other.region.or(self.region),
+            identity_cache: other.identity_cache.or(self.identity_cache),
+            sso: other.sso.or(self.sso),
+        }
+    }
 }
 
 /// AWS SDK identity cache configuration
@@ -74,3 +165,23 @@ pub struct IdentityCache {
     pub load_timeout: Option<u64>
 }
 
+
+/// IAM Identity Center (SSO) configuration, similar to `sso_*` settings of an AWS CLI profile
+#[derive(Debug, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct AwsSsoConfig {
+    /// AWS access portal URL, such as `https://my-org.awsapps.com/start`
+    pub start_url: String,
+
+    /// Region of IAM Identity Center, such as `eu-west-1`
+    pub region: String,
+
+    /// AWS account ID to get role credentials for
+    pub account_id: String,
+
+    /// Permission set role name to get credentials for
+    pub role_name: String,
+
+    /// SSO session name, as `sso_session` in AWS CLI config. Token is cached under session name,
+    /// allowing to share token with AWS CLI using the same session. Default: token is cached under start URL
+    pub session_name: Option<String>
+}
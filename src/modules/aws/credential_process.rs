@@ -0,0 +1,51 @@
+use serde::Serialize;
+use aws_smithy_types::DateTime;
+use aws_smithy_types::date_time::Format;
+use log::warn;
+
+use crate::modules::aws::client::session_credentials_from_outputs;
+use crate::modules::variables::VariableOutput;
+
+/// Version of credential_process output document
+const CREDENTIAL_PROCESS_VERSION: u8 = 1;
+
+/// Credentials document expected by AWS `credential_process` setting. 
+/// See https://docs.aws.amazon.com/sdkref/latest/guide/feature-process-credentials.html
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct AwsCredentialProcessOutput {
+    pub version: u8,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_token: Option<String>,
+
+    /// ISO8601 timestamp. Credentials without expiration are not refreshed by AWS SDKs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expiration: Option<String>
+}
+
+impl AwsCredentialProcessOutput {
+
+    /// Build document from credentials variables as output by Inputs generating AWS credentials
+    pub fn from_outputs(outputs: &[VariableOutput]) -> Result<AwsCredentialProcessOutput, anyhow::Error> {
+        let creds = session_credentials_from_outputs(outputs)?;
+
+        let expiration = match creds.expiry() {
+            Some(exp) => Some(DateTime::from(exp).fmt(Format::DateTime)?),
+            None => {
+                warn!("AWS credentials have no expiration: AWS SDKs and tools won't refresh them");
+                None
+            }
+        };
+
+        Ok(AwsCredentialProcessOutput {
+            version: CREDENTIAL_PROCESS_VERSION,
+            access_key_id: creds.access_key_id().to_string(),
+            secret_access_key: creds.secret_access_key().to_string(),
+            session_token: creds.session_token().map(String::from),
+            expiration
+        })
+    }
+}
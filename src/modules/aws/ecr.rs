@@ -0,0 +1,87 @@
+use serde::Deserialize;
+use async_trait::async_trait;
+use anyhow::{self, Context};
+use schemars::JsonSchema;
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use crate::core::{ResolveTo, NovopsContext};
+use crate::modules::aws::client::get_client;
+
+/// Generate an ECR authorization token to pull and push images
+#[derive(Debug, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct AwsEcrAuthorizationTokenInput {
+
+    pub aws_ecr_authorization_token: AwsEcrAuthorizationToken
+}
+
+/// Generate an ECR authorization token
+/// 
+/// Maps to GetAuthorizationToken API. See https://docs.aws.amazon.com/AmazonECR/latest/APIReference/API_GetAuthorizationToken.html
+#[derive(Debug, Deserialize, Clone, PartialEq, JsonSchema, Default)]
+pub struct AwsEcrAuthorizationToken {
+
+    /// Output format. Default: `password`
+    pub format: Option<AwsEcrTokenFormat>,
+
+    /// Registry hosts authenticated in Docker config, such as `123456789012.dkr.ecr.eu-west-1.amazonaws.com`. 
+    /// Default to account registry in current region. 
+    pub registries: Option<Vec<String>>,
+
+    /// Named AWS backend from `config.backends.aws` to use. Default to `config.aws`.
+    pub backend: Option<String>
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, JsonSchema, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AwsEcrTokenFormat {
+    /// Registry password, used with `docker login --username AWS --password-stdin`
+    #[default]
+    Password,
+
+    /// Docker `config.json` authenticating registries
+    DockerConfig
+}
+
+#[async_trait]
+impl ResolveTo<String> for AwsEcrAuthorizationTokenInput {
+    async fn resolve(&self, ctx: &NovopsContext) -> Result<String, anyhow::Error> {
+
+        let client = get_client(ctx, &self.aws_ecr_authorization_token.backend).await?;
+
+        let output = client.get_ecr_authorization_token().await?;
+
+        let data = output.authorization_data().first()
+            .ok_or(anyhow::anyhow!("No authorization data in ECR response"))?;
+
+        // token is base64 encoded `AWS:<password>`
+        let token = data.authorization_token()
+            .ok_or(anyhow::anyhow!("No authorization token in ECR response"))?;
+
+        match self.aws_ecr_authorization_token.format.unwrap_or_default() {
+            AwsEcrTokenFormat::Password => {
+                let decoded = String::from_utf8(BASE64.decode(token)?)
+                    .with_context(|| "Couldn't decode ECR authorization token as UTF-8")?;
+
+                let (_, password) = decoded.split_once(':')
+                    .ok_or(anyhow::anyhow!("ECR authorization token isn't in expected 'user:password' format"))?;
+
+                Ok(password.to_string())
+            },
+            AwsEcrTokenFormat::DockerConfig => {
+                let registries = match &self.aws_ecr_authorization_token.registries {
+                    Some(r) => r.clone(),
+                    None => {
+                        let endpoint = data.proxy_endpoint()
+                            .ok_or(anyhow::anyhow!("No proxy endpoint in ECR response, set `registries` explicitly"))?;
+                        vec![endpoint.trim_start_matches("https://").to_string()]
+                    }
+                };
+
+                let auths: serde_json::Map<String, serde_json::Value> = registries.into_iter()
+                    .map(|r| (r, serde_json::json!({ "auth": token })))
+                    .collect();
+
+                Ok(serde_json::to_string_pretty(&serde_json::json!({ "auths": auths }))?)
+            }
+        }
+    }
+}
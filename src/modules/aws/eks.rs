@@ -0,0 +1,33 @@
+use serde::Deserialize;
+use async_trait::async_trait;
+use anyhow;
+use schemars::JsonSchema;
+use crate::core::{ResolveTo, NovopsContext};
+use crate::modules::aws::client::get_client;
+
+/// Generate an EKS bearer token to authenticate with a cluster, as `aws eks get-token` would
+#[derive(Debug, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct AwsEksTokenInput {
+
+    pub aws_eks_token: AwsEksToken
+}
+
+/// Generate an EKS bearer token (`k8s-aws-v1.` format) valid for 15 minutes
+#[derive(Debug, Deserialize, Clone, PartialEq, JsonSchema, Default)]
+pub struct AwsEksToken {
+
+    /// EKS cluster name
+    pub cluster_name: String,
+
+    /// Named AWS backend from `config.backends.aws` to use. Default to `config.aws`. Region must match cluster region.
+    pub backend: Option<String>
+}
+
+#[async_trait]
+impl ResolveTo<String> for AwsEksTokenInput {
+    async fn resolve(&self, ctx: &NovopsContext) -> Result<String, anyhow::Error> {
+        let client = get_client(ctx, &self.aws_eks_token.backend).await?;
+
+        client.get_eks_token(&self.aws_eks_token.cluster_name).await
+    }
+}
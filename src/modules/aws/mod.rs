@@ -1,6 +1,16 @@
 pub mod assume_role;
+pub mod web_identity;
 pub mod config;
 pub mod client;
 pub mod ssm;
 pub mod secretsmanager;
-pub mod s3;
\ No newline at end of file
+pub mod s3;
+pub mod ecr;
+pub mod codeartifact;
+pub mod eks;
+pub mod rds;
+pub mod caller_identity;
+pub mod sso;
+pub mod prefetch;
+pub mod profile;
+pub mod credential_process;
\ No newline at end of file
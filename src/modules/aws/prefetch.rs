@@ -0,0 +1,97 @@
+use std::collections::{BTreeMap, BTreeSet};
+use log::{debug, warn};
+
+use crate::core::{NovopsContext, StringResolvableInput, BytesResolvableInput};
+use crate::modules::aws::client::get_client;
+use crate::modules::aws::secretsmanager::AwsSecretsManagerSecretInput;
+use crate::modules::files::FileInput;
+use crate::modules::variables::VariableInput;
+
+/// SSM parameter names to read by backend and decryption flag
+type SsmParameterBatches = BTreeMap<(Option<String>, Option<bool>), BTreeSet<String>>;
+
+/// Secret IDs to read by backend
+type SecretBatches = BTreeMap<Option<String>, BTreeSet<String>>;
+
+/**
+ * Read SSM parameters and Secrets Manager secrets used by variables and files with batch APIs
+ * before resolving them, so that Inputs resolve from already read values.
+ *
+ * Only Inputs with a plain string name or ID (and no secret version) are read this way.
+ * Batch errors are not fatal: Inputs are then read individually, reporting errors as usual.
+ */
+pub async fn prefetch_aws_inputs(ctx: &NovopsContext, variables: &[VariableInput], files: &[FileInput]) {
+
+    let mut ssm_batches = SsmParameterBatches::new();
+    let mut secret_batches = SecretBatches::new();
+
+    for v in variables {
+        collect_string_input(&v.value, &mut ssm_batches, &mut secret_batches);
+    }
+
+    for f in files {
+        match &f.content {
+            BytesResolvableInput::AwsSecretsManagerSecretInput(s) => collect_secret(s, &mut secret_batches),
+            BytesResolvableInput::StringResolvableInput(s) => collect_string_input(s, &mut ssm_batches, &mut secret_batches),
+            _ => {}
+        }
+    }
+
+    // a single read doesn't need a batch
+    for ((backend, decrypt), names) in ssm_batches.into_iter().filter(|(_, names)| names.len() > 1) {
+        let names: Vec<String> = names.into_iter().collect();
+        debug!("Reading SSM parameters {:?} with batch requests", &names);
+
+        let result = match get_client(ctx, &backend).await {
+            Ok(client) => client.prefetch_ssm_parameters(&names, decrypt).await,
+            Err(e) => Err(e)
+        };
+
+        if let Err(e) = result {
+            warn!("Couldn't read SSM parameters with batch requests, they will be read individually: {:?}", e);
+        }
+    }
+
+    for (backend, ids) in secret_batches.into_iter().filter(|(_, ids)| ids.len() > 1) {
+        let ids: Vec<String> = ids.into_iter().collect();
+        debug!("Reading Secrets Manager secrets {:?} with batch requests", &ids);
+
+        let result = match get_client(ctx, &backend).await {
+            Ok(client) => client.prefetch_secret_values(&ids).await,
+            Err(e) => Err(e)
+        };
+
+        if let Err(e) = result {
+            warn!("Couldn't read Secrets Manager secrets with batch requests, they will be read individually: {:?}", e);
+        }
+    }
+}
+
+fn collect_string_input(input: &StringResolvableInput, ssm_batches: &mut SsmParameterBatches, secret_batches: &mut SecretBatches) {
+    match input {
+        StringResolvableInput::AwsSSMParamStoreInput(p) => {
+            if let StringResolvableInput::String(name) = p.aws_ssm_parameter.name.as_ref() {
+                ssm_batches.entry((p.aws_ssm_parameter.backend.clone(), p.aws_ssm_parameter.with_decryption))
+                    .or_default()
+                    .insert(name.clone());
+            }
+        },
+        StringResolvableInput::AwsSecretsManagerSecretInput(s) => collect_secret(s, secret_batches),
+        _ => {}
+    }
+}
+
+fn collect_secret(input: &AwsSecretsManagerSecretInput, secret_batches: &mut SecretBatches) {
+    let secret = &input.aws_secret;
+
+    // batch API only reads current version
+    if secret.version_id.is_some() || secret.version_stage.is_some() {
+        return;
+    }
+
+    if let StringResolvableInput::String(id) = secret.id.as_ref() {
+        secret_batches.entry(secret.backend.clone())
+            .or_default()
+            .insert(id.clone());
+    }
+}
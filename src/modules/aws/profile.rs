@@ -0,0 +1,134 @@
+use std::collections::HashSet;
+use anyhow::Context;
+use serde::Deserialize;
+use schemars::JsonSchema;
+
+use crate::core::{ResolveTo, NovopsContext, BytesResolvableInput};
+use crate::modules::aws::client::build_mutable_client_config_from_context;
+use crate::modules::aws::config::AwsInput;
+use crate::modules::files::{FileInput, FileOutput};
+use crate::modules::hashivault::config::HashiVaultInput;
+use crate::modules::variables::VariableOutput;
+
+/// AWS credentials profiles written in generated AWS credentials and config files
+#[derive(Debug, Deserialize, Clone, PartialEq, JsonSchema, Default)]
+pub struct AwsCredentialsProfilesInput {
+    /// Profiles to output, in addition to `credentials_profile` of environment `aws` and `hashivault.aws` Inputs
+    pub profiles: Option<Vec<AwsCredentialsProfileInput>>,
+
+    /// Profile set as `AWS_PROFILE`, must be one of output profiles. 
+    /// Default to the only output profile. `AWS_PROFILE` is not set if several profiles are output without default.
+    pub default_profile: Option<String>
+}
+
+/// Named profile with credentials generated by exactly one of `aws` or `hashivault`.
+/// 
+/// `use_for_inputs` and `credentials_profile` can't be set on profile Inputs.
+#[derive(Debug, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct AwsCredentialsProfileInput {
+    /// Profile name
+    pub name: String,
+
+    /// AWS credentials to generate, such as `assume_role`
+    pub aws: Option<AwsInput>,
+
+    /// Hashicorp Vault AWS Secret Engine credentials to generate
+    pub hashivault: Option<HashiVaultInput>
+}
+
+/// AWS credentials to write as a named profile
+#[derive(Debug, Clone, PartialEq)]
+pub struct AwsProfileCredentials {
+    pub profile: String,
+
+    /// Region written in profile config
+    pub region: Option<String>,
+
+    /// Credentials as output by Input: AWS_ACCESS_KEY_ID, AWS_SECRET_ACCESS_KEY and optional AWS_SESSION_TOKEN
+    pub credentials: Vec<VariableOutput>
+}
+
+/**
+ * Generate AWS shared credentials and config files with a section for each profile in secure working directory.
+ * 
+ * Outputs files with variables `AWS_SHARED_CREDENTIALS_FILE` and `AWS_CONFIG_FILE` pointing to them, 
+ * and `AWS_PROFILE` set to default profile, or to the only profile if no default is set.
+ */
+pub async fn build_profile_outputs(ctx: &NovopsContext, profiles: &[AwsProfileCredentials], default_profile: &Option<String>) 
+        -> Result<(Vec<VariableOutput>, Vec<FileOutput>), anyhow::Error> {
+
+    let mut seen = HashSet::new();
+    let mut credentials_file = String::new();
+    let mut config_file = String::new();
+
+    for p in profiles {
+        if !seen.insert(&p.profile) {
+            return Err(anyhow::anyhow!("AWS credentials profile '{}' is used by more than one Input", &p.profile));
+        }
+
+        let get = |name: &str| p.credentials.iter().find(|v| v.name == name).map(|v| v.value.clone());
+
+        let access_key_id = get("AWS_ACCESS_KEY_ID")
+            .with_context(|| format!("AWS_ACCESS_KEY_ID not found in credentials for profile '{}'", &p.profile))?;
+        let secret_access_key = get("AWS_SECRET_ACCESS_KEY")
+            .with_context(|| format!("AWS_SECRET_ACCESS_KEY not found in credentials for profile '{}'", &p.profile))?;
+
+        credentials_file.push_str(&format!("[{}]\naws_access_key_id = {}\naws_secret_access_key = {}\n", 
+            &p.profile, access_key_id, secret_access_key));
+
+        if let Some(token) = get("AWS_SESSION_TOKEN") {
+            credentials_file.push_str(&format!("aws_session_token = {}\n", token));
+        }
+        credentials_file.push('\n');
+
+        config_file.push_str(&format!("[profile {}]\n", &p.profile));
+        if let Some(r) = &p.region {
+            config_file.push_str(&format!("region = {}\n", r));
+        }
+        config_file.push('\n');
+    }
+
+    let default_profile = match (default_profile, profiles) {
+        (Some(name), _) if seen.contains(name) => Some(name.clone()),
+        (Some(name), _) => {
+            let names: Vec<&String> = profiles.iter().map(|p| &p.profile).collect();
+            return Err(anyhow::anyhow!("AWS default profile '{}' is not an output profile. Output profiles: {:?}", name, names));
+        },
+        (None, [only]) => Some(only.profile.clone()),
+        (None, _) => None
+    };
+
+    let credentials_output = profile_file(ctx, "AWS_SHARED_CREDENTIALS_FILE", credentials_file).await?;
+    let config_output = profile_file(ctx, "AWS_CONFIG_FILE", config_file).await?;
+
+    // file variables are output with files
+    let variables = default_profile
+        .map(|profile| VariableOutput { name: String::from("AWS_PROFILE"), value: profile })
+        .into_iter()
+        .collect();
+
+    Ok((variables, vec![credentials_output, config_output]))
+}
+
+/// Region of AWS config used for backend (named backend or default config), written in profile config
+pub fn backend_region(ctx: &NovopsContext, backend: &Option<String>) -> Result<Option<String>, anyhow::Error> {
+    Ok(build_mutable_client_config_from_context(ctx, backend)?.region)
+}
+
+/// Generate file in secure working directory with a variable pointing to it
+async fn profile_file(ctx: &NovopsContext, variable: &str, content: String) -> Result<FileOutput, anyhow::Error> {
+    let input = FileInput {
+        name: None,
+        dest: None,
+        symlink: None,
+        variable: Some(variable.to_string()),
+        content: BytesResolvableInput::ByteVec(content.into_bytes()),
+        retry: None,
+        timeout: None,
+        optional: None,
+        default: None
+    };
+
+    input.resolve(ctx).await
+        .with_context(|| format!("Couldn't generate AWS profile file for {}", variable))
+}
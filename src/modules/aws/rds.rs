@@ -0,0 +1,46 @@
+use serde::Deserialize;
+use async_trait::async_trait;
+use anyhow;
+use schemars::JsonSchema;
+use crate::core::{ResolveTo, NovopsContext};
+use crate::modules::aws::client::get_client;
+
+/// Generate an RDS / Aurora IAM database authentication token, used as database password
+#[derive(Debug, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct AwsRdsAuthTokenInput {
+
+    pub aws_rds_auth_token: AwsRdsAuthToken
+}
+
+/// Generate an RDS IAM database authentication token valid for 15 minutes, as `aws rds generate-db-auth-token` would. 
+/// 
+/// Token is generated locally with current AWS credentials, without request to AWS.
+#[derive(Debug, Deserialize, Clone, PartialEq, JsonSchema, Default)]
+pub struct AwsRdsAuthToken {
+
+    /// Database endpoint hostname, such as `mydb.123456789012.eu-west-1.rds.amazonaws.com`
+    pub hostname: String,
+
+    /// Database port, such as 5432 or 3306
+    pub port: u16,
+
+    /// Database user with IAM authentication enabled
+    pub username: String,
+
+    /// Database region. Default to AWS config region. 
+    pub region: Option<String>,
+
+    /// Named AWS backend from `config.backends.aws` to use. Default to `config.aws`.
+    pub backend: Option<String>
+}
+
+#[async_trait]
+impl ResolveTo<String> for AwsRdsAuthTokenInput {
+    async fn resolve(&self, ctx: &NovopsContext) -> Result<String, anyhow::Error> {
+        let token = &self.aws_rds_auth_token;
+
+        let client = get_client(ctx, &token.backend).await?;
+
+        client.get_rds_auth_token(&token.hostname, token.port, &token.username, &token.region).await
+    }
+}
@@ -1,9 +1,11 @@
-use crate::core::{ResolveTo, NovopsContext};
+use crate::core::{ResolveTo, NovopsContext, StringResolvableInput};
 use serde::Deserialize;
 use async_trait::async_trait;
 use schemars::JsonSchema;
 use log::debug;
-use crate::modules::aws::client::get_client;
+use anyhow::Context;
+use crate::modules::aws::client::{get_client, S3ObjectParams};
+use crate::modules::json::{extract_json_value, extract_json_bytes};
 
 /// Reference an S3 object
 #[derive(Debug, Deserialize, Clone, PartialEq, JsonSchema)]
@@ -18,25 +20,83 @@ pub struct AwsS3Object {
     pub bucket: String,
 
     /// S3 object key
-    pub key: String,
+    /// 
+    /// Can be a plain string or any Input resolving to a string, such as `template` or another secret.
+    pub key: Box<StringResolvableInput>,
 
     /// Optional bucket region name
-    pub region: Option<String>
+    pub region: Option<String>,
+
+    /// Object version to retrieve. Default to latest version.
+    pub version_id: Option<String>,
+
+    /// Base64 encoded 256-bit key for objects encrypted with a customer-provided key (SSE-C). 
+    /// 
+    /// Can be a plain string or any Input resolving to a string, such as another secret.
+    pub sse_customer_key: Option<Box<StringResolvableInput>>,
+
+    /// Account ID of expected bucket owner. Request fails if bucket is owned by another account.
+    pub expected_bucket_owner: Option<String>,
+
+    /// Named AWS backend from `config.backends.aws` to use. Default to `config.aws`.
+    pub backend: Option<String>,
+
+    /// Extract value at JSON Pointer from JSON object content, such as `/password`
+    pub extract: Option<String>
+}
+
+impl AwsS3ObjectInput {
+    /// Names of other variables this Input references, which must be resolved first
+    pub fn referenced_variables(&self) -> Result<Vec<String>, anyhow::Error> {
+        let mut result = self.aws_s3_object.key.referenced_variables()?;
+        if let Some(k) = &self.aws_s3_object.sse_customer_key {
+            result.extend(k.referenced_variables()?);
+        }
+        Ok(result)
+    }
+
+    async fn retrieve_object_bytes(&self, ctx: &NovopsContext) -> Result<Vec<u8>, anyhow::Error> {
+        let key = self.aws_s3_object.key.resolve(ctx).await?;
+
+        let sse_customer_key = match &self.aws_s3_object.sse_customer_key {
+            Some(k) => Some(k.resolve(ctx).await
+                .with_context(|| format!("Couldn't resolve SSE-C customer key for S3 object '{}/{}'", &self.aws_s3_object.bucket, &key))?),
+            None => None
+        };
+
+        let client = get_client(ctx, &self.aws_s3_object.backend).await?;
+
+        let result = client.get_s3_object(&S3ObjectParams {
+            bucket: self.aws_s3_object.bucket.clone(),
+            key: key.clone(),
+            region: self.aws_s3_object.region.clone(),
+            version_id: self.aws_s3_object.version_id.clone(),
+            sse_customer_key,
+            expected_bucket_owner: self.aws_s3_object.expected_bucket_owner.clone()
+        }).await?;
+        
+        debug!("Got file {:} from S3 bucket {:}", &key, &self.aws_s3_object.bucket);
+        
+        Ok(result.body.collect().await?.into_bytes().to_vec())
+    }
 }
 
 #[async_trait]
 impl ResolveTo<String> for AwsS3ObjectInput {
     async fn resolve(&self, ctx: &NovopsContext) -> Result<String, anyhow::Error> {
-        let client = get_client(ctx).await;
+        let content = String::from_utf8(self.retrieve_object_bytes(ctx).await?)
+            .with_context(|| format!("Couldn't convert S3 object from bucket '{}' to UTF-8 String. \
+                Either use File input for binary data or make sure it's a valid UTF-8 string.", &self.aws_s3_object.bucket))?;
 
-        let result = client.get_s3_object(
-            &self.aws_s3_object.bucket, 
-            &self.aws_s3_object.key, 
-            &self.aws_s3_object.region
-        ).await?;
-        
-        debug!("Got file {:} from S3 bucket {:}", &self.aws_s3_object.key, &self.aws_s3_object.bucket);
-        
-        Ok(String::from_utf8(result.body.collect().await?.into_bytes().to_vec())?)
+        extract_json_value(ctx, content, &self.aws_s3_object.extract)
+    }
+}
+
+#[async_trait]
+impl ResolveTo<Vec<u8>> for AwsS3ObjectInput {
+    async fn resolve(&self, ctx: &NovopsContext) -> Result<Vec<u8>, anyhow::Error> {
+        let content = self.retrieve_object_bytes(ctx).await?;
+
+        extract_json_bytes(ctx, content, &self.aws_s3_object.extract)
     }
 }
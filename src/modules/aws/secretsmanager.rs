@@ -5,8 +5,9 @@ use async_trait::async_trait;
 use schemars::JsonSchema;
 use std::default::Default;
 
-use crate::core::{ResolveTo, NovopsContext};
+use crate::core::{ResolveTo, NovopsContext, StringResolvableInput};
 use crate::modules::aws::client::get_client;
+use crate::modules::json::{extract_json_value, extract_json_bytes};
 
 /// Reference an AWS Secret Manager secret
 #[derive(Debug, Deserialize, Clone, PartialEq, JsonSchema)]
@@ -23,7 +24,9 @@ pub struct AwsSecretsManagerSecretInput {
 pub struct AwsSecretsManagerSecret {
     
     /// Secret ID
-    pub id: String,
+    /// 
+    /// Can be a plain string or any Input resolving to a string, such as `template` or another secret.
+    pub id: Box<StringResolvableInput>,
 
     
     /// The unique identifier of the version of the secret to retrieve. 
@@ -32,22 +35,40 @@ pub struct AwsSecretsManagerSecret {
     
     /// The staging label of the version of the secret to retrieve.
     pub version_stage: Option<String>,
+
+    /// Named AWS backend from `config.backends.aws` to use. Default to `config.aws`.
+    pub backend: Option<String>,
+
+    /// Extract value at JSON Pointer from JSON secret, such as `/password` for `{"username": "...", "password": "..."}`
+    pub extract: Option<String>,
+}
+
+impl AwsSecretsManagerSecretInput {
+    /// Names of other variables this Input references, which must be resolved first
+    pub fn referenced_variables(&self) -> Result<Vec<String>, anyhow::Error> {
+        self.aws_secret.id.referenced_variables()
+    }
 }
 
 #[async_trait]
 impl ResolveTo<Vec<u8>> for AwsSecretsManagerSecretInput {
     async fn resolve(&self, ctx: &NovopsContext) -> Result<Vec<u8>, anyhow::Error> {
 
-        let output = retrieve_secret(ctx, self).await
-            .with_context(|| format!("Couldn't retrieve secret {:}", &self.aws_secret.id))?;
+        let id = self.aws_secret.id.resolve(ctx).await?;
 
-        return if let Some(s) =  output.secret_string() {
-            Ok(s.to_string().into_bytes())
+        let output = retrieve_secret(ctx, &id, self).await
+            .with_context(|| format!("Couldn't retrieve secret {:}", &id))?;
+
+        let value = if let Some(s) =  output.secret_string() {
+            s.to_string().into_bytes()
         } else if let Some(s) = output.secret_binary() {
-            Ok(s.clone().into_inner())
+            s.clone().into_inner()
         } else {
-            Err(anyhow::format_err!("Secret value was neither string nor binary, got response: {:?}", output))
-        }
+            return Err(anyhow::format_err!("Secret value was neither string nor binary, got response: {:?}", output))
+        };
+
+        extract_json_bytes(ctx, value, &self.aws_secret.extract)
+            .with_context(|| format!("Couldn't extract value from secret {:}", &id))
     }
 }
 
@@ -55,28 +76,31 @@ impl ResolveTo<Vec<u8>> for AwsSecretsManagerSecretInput {
 impl ResolveTo<String> for AwsSecretsManagerSecretInput {
     async fn resolve(&self, ctx: &NovopsContext) -> Result<String, anyhow::Error> {
 
-        let output = retrieve_secret(ctx, self).await?;
+        let id = self.aws_secret.id.resolve(ctx).await?;
 
-        return if let Some(s) = output.secret_string() {
-            Ok(s.to_string())
+        let output = retrieve_secret(ctx, &id, self).await?;
+
+        let value = if let Some(s) = output.secret_string() {
+            s.to_string()
         } else if let Some (s) = output.secret_binary() {
             let binary = s.clone().into_inner();
-            let result = String::from_utf8(binary)
+            String::from_utf8(binary)
                 .with_context(|| format!("Couldn't convert bytes from Secrets Manager secret '{}' to UTF-8 String. \
-                Non-UTF-8 binary data can't be used as Variable input yet. Either use File input for binary data or make sure it's a valid UTF-8 string.", self.aws_secret.id))?;
-            Ok(result)
+                Non-UTF-8 binary data can't be used as Variable input yet. Either use File input for binary data or make sure it's a valid UTF-8 string.", &id))?
         } else {
-            Err(anyhow::format_err!("Secret value was neither string nor binary, got response: {:?}", output))
+            return Err(anyhow::format_err!("Secret value was neither string nor binary, got response: {:?}", output))
         };
-        
+
+        extract_json_value(ctx, value, &self.aws_secret.extract)
+            .with_context(|| format!("Couldn't extract value from secret {:}", &id))
     }
 }
 
-async fn retrieve_secret(ctx: &NovopsContext, input: &AwsSecretsManagerSecretInput) -> Result<GetSecretValueOutput, anyhow::Error>{
-    let client = get_client(ctx).await;
+async fn retrieve_secret(ctx: &NovopsContext, id: &str, input: &AwsSecretsManagerSecretInput) -> Result<GetSecretValueOutput, anyhow::Error>{
+    let client = get_client(ctx, &input.aws_secret.backend).await?;
 
     let output = client.get_secret_value(
-        input.aws_secret.id.as_str(), 
+        id, 
         input.aws_secret.version_id.clone(), 
         input.aws_secret.version_stage.clone()
     ).await?;
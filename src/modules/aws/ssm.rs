@@ -1,10 +1,13 @@
+use std::collections::{HashMap, HashSet};
 use serde::Deserialize;
 use async_trait::async_trait;
 use anyhow;
 use schemars::JsonSchema;
 use std::default::Default;
-use crate::core::{ResolveTo, NovopsContext};
+use crate::core::{ResolveTo, NovopsContext, StringResolvableInput};
 use crate::modules::aws::client::get_client;
+use crate::modules::variables::{VariableOutput, upper_snake_case};
+use crate::modules::json::extract_json_value;
 
 /// Reference an SSM Parameter config or secret
 #[derive(Debug, Deserialize, Clone, PartialEq, JsonSchema)]
@@ -20,21 +23,38 @@ pub struct AwsSSMParamStoreInput {
 pub struct AwsSSMParameter {
     
     /// Parameter name
-    pub name: String,
+    /// 
+    /// Can be a plain string or any Input resolving to a string, such as `template` or another secret.
+    pub name: Box<StringResolvableInput>,
 
     /// Return decrypted values for secure string parameters. This flag is ignored for String and StringList parameter types.
-    pub with_decryption: Option<bool>
+    pub with_decryption: Option<bool>,
+
+    /// Named AWS backend from `config.backends.aws` to use. Default to `config.aws`.
+    pub backend: Option<String>,
+
+    /// Extract value at JSON Pointer from JSON parameter value, such as `/password`
+    pub extract: Option<String>
 }
 
 
+impl AwsSSMParamStoreInput {
+    /// Names of other variables this Input references, which must be resolved first
+    pub fn referenced_variables(&self) -> Result<Vec<String>, anyhow::Error> {
+        self.aws_ssm_parameter.name.referenced_variables()
+    }
+}
+
 #[async_trait]
 impl ResolveTo<String> for AwsSSMParamStoreInput {
     async fn resolve(&self, ctx: &NovopsContext) -> Result<String, anyhow::Error> {
 
-        let client = get_client(ctx).await;
+        let name = self.aws_ssm_parameter.name.resolve(ctx).await?;
+
+        let client = get_client(ctx, &self.aws_ssm_parameter.backend).await?;
 
         let result = client.get_ssm_parameter(
-            &self.aws_ssm_parameter.name, 
+            &name, 
             self.aws_ssm_parameter.with_decryption
         ).await?;
         
@@ -42,6 +62,94 @@ impl ResolveTo<String> for AwsSSMParamStoreInput {
             .value().ok_or(anyhow::anyhow!("Couldn't unwrap parameter value"))?
             .to_string();
         
-        Ok(value)
+        extract_json_value(ctx, value, &self.aws_ssm_parameter.extract)
+    }
+}
+
+/// Load all SSM Parameters under a path hierarchy as variables
+/// 
+/// Maps to GetParametersByPath API. See https://docs.aws.amazon.com/systems-manager/latest/APIReference/API_GetParametersByPath.html
+#[derive(Debug, Deserialize, Clone, PartialEq, JsonSchema, Default)]
+pub struct AwsSSMParametersByPathInput {
+
+    /// Parameter path hierarchy, such as `/myapp/prod`
+    pub path: String,
+
+    /// Also load parameters in nested paths. Default: false
+    pub recursive: Option<bool>,
+
+    /// Return decrypted values for secure string parameters. Default: true
+    pub with_decryption: Option<bool>,
+
+    /// Remove `path` from parameter names to build variable names. Default: true
+    pub strip_prefix: Option<bool>,
+
+    /// Convert parameter names to upper snake case variable names, such as `db/password` to `DB_PASSWORD`: 
+    /// characters other than letters and digits are replaced by `_`. Default: true
+    pub upper_snake_case: Option<bool>,
+
+    /// Explicit variable names by full parameter name, such as `/myapp/prod/db/password: DATABASE_PASSWORD`. 
+    /// Takes precedence over other name rules.
+    pub rename: Option<HashMap<String, String>>,
+
+    /// Named AWS backend from `config.backends.aws` to use. Default to `config.aws`.
+    pub backend: Option<String>
+}
+
+impl AwsSSMParametersByPathInput {
+
+    /// Variable name for parameter following name rules
+    fn variable_name(&self, parameter_name: &str) -> String {
+        if let Some(name) = self.rename.as_ref().and_then(|r| r.get(parameter_name)) {
+            return name.clone();
+        }
+
+        let name = if self.strip_prefix.unwrap_or(true) {
+            parameter_name.strip_prefix(self.path.trim_end_matches('/')).unwrap_or(parameter_name)
+                .trim_start_matches('/')
+        } else {
+            parameter_name
+        };
+
+        if self.upper_snake_case.unwrap_or(true) {
+            upper_snake_case(name)
+        } else {
+            name.to_string()
+        }
+    }
+}
+
+#[async_trait]
+impl ResolveTo<Vec<VariableOutput>> for AwsSSMParametersByPathInput {
+    async fn resolve(&self, ctx: &NovopsContext) -> Result<Vec<VariableOutput>, anyhow::Error> {
+
+        let client = get_client(ctx, &self.backend).await?;
+
+        let parameters = client.get_ssm_parameters_by_path(
+            &self.path, 
+            self.recursive.unwrap_or(false),
+            self.with_decryption
+        ).await?;
+
+        let mut names = HashSet::new();
+        let mut variables = vec![];
+        for param in parameters {
+            let param_name = param.name().ok_or(anyhow::anyhow!("Couldn't unwrap parameter name under path {}", &self.path))?;
+            let value = param.value().ok_or(anyhow::anyhow!("Couldn't unwrap parameter value for {}", param_name))?;
+
+            let name = self.variable_name(param_name);
+            if name.is_empty() {
+                return Err(anyhow::anyhow!("Parameter {} resolved to an empty variable name. Use `rename` to set its name.", param_name));
+            }
+
+            if !names.insert(name.clone()) {
+                return Err(anyhow::anyhow!("Several parameters under path {} resolved to variable name {}. Use `rename` to set distinct names.", 
+                    &self.path, name));
+            }
+
+            variables.push(VariableOutput { name, value: value.to_string() });
+        }
+
+        Ok(variables)
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,333 @@
+use std::fs;
+use std::io::Write;
+use std::os::unix::prelude::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::Context;
+use aws_config::{BehaviorVersion, Region};
+use aws_sdk_sts::config::Credentials;
+use aws_smithy_types::date_time::{DateTime, Format};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use sha1::{Sha1, Digest};
+use tokio::sync::Mutex;
+
+use super::config::AwsSsoConfig;
+
+/// Cached token or client registration expiring sooner than this is considered expired
+const SSO_EXPIRY_MARGIN: Duration = Duration::from_secs(60);
+
+/// Grant type to exchange device code for an access token
+const DEVICE_CODE_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+/// Seconds added to polling interval when asked to slow down (RFC 8628)
+const SLOW_DOWN_INCREMENT: Duration = Duration::from_secs(5);
+
+/// Only one device authorization flow at a time: concurrent clients wait and reuse cached token
+static SSO_LOGIN_LOCK: Mutex<()> = Mutex::const_new(());
+
+/**
+ * SSO token as cached by AWS CLI and SDKs under ~/.aws/sso/cache.
+ * Client registration is cached along with token to be reused on next authorization.
+ */
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct SsoTokenCache {
+    start_url: String,
+    region: String,
+    access_token: String,
+    expires_at: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    client_id: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    client_secret: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    registration_expires_at: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    refresh_token: Option<String>
+}
+
+/**
+ * Get role credentials for SSO config using cached SSO token. If token is missing, expired or rejected,
+ * run device authorization flow: user is prompted on stderr to authorize access in a browser.
+ */
+pub async fn get_sso_role_credentials(sso: &AwsSsoConfig, endpoint: &Option<String>) -> Result<Credentials, anyhow::Error> {
+    let mut loader = aws_config::defaults(BehaviorVersion::latest())
+        .region(Region::new(sso.region.clone()))
+        .no_credentials();
+
+    if let Some(e) = endpoint {
+        loader = loader.endpoint_url(e);
+    }
+
+    let sdk_config = loader.load().await;
+    let oidc_client = aws_sdk_ssooidc::Client::new(&sdk_config);
+    let sso_client = aws_sdk_sso::Client::new(&sdk_config);
+
+    let cache_path = sso_token_cache_path(sso)?;
+
+    let access_token = get_access_token(sso, &oidc_client, &cache_path, false).await?;
+    let result = match get_role_credentials(&sso_client, sso, &access_token).await {
+        Err(e) if e.as_service_error().is_some_and(|s| s.is_unauthorized_exception()) => {
+            info!("Cached SSO token for {} was rejected, authorizing access again", &sso.start_url);
+            let access_token = get_access_token(sso, &oidc_client, &cache_path, true).await?;
+            get_role_credentials(&sso_client, sso, &access_token).await
+        },
+        other => other
+    };
+
+    let output = result.with_context(|| format!("Couldn't get SSO role credentials for role {} in account {}",
+        &sso.role_name, &sso.account_id))?;
+
+    let creds = output.role_credentials()
+        .ok_or(anyhow::anyhow!("No role credentials in SSO response for role {} in account {}", &sso.role_name, &sso.account_id))?;
+
+    let access_key_id = creds.access_key_id()
+        .ok_or(anyhow::anyhow!("Access Key ID not found in SSO role credentials"))?;
+
+    let secret_access_key = creds.secret_access_key()
+        .ok_or(anyhow::anyhow!("Secret Access Key not found in SSO role credentials"))?;
+
+    Ok(Credentials::new(
+        access_key_id,
+        secret_access_key,
+        creds.session_token().map(String::from),
+        Some(UNIX_EPOCH + Duration::from_millis(creds.expiration() as u64)),
+        "novops-sso"
+    ))
+}
+
+async fn get_role_credentials(client: &aws_sdk_sso::Client, sso: &AwsSsoConfig, access_token: &str)
+        -> Result<aws_sdk_sso::operation::get_role_credentials::GetRoleCredentialsOutput,
+            aws_sdk_sso::error::SdkError<aws_sdk_sso::operation::get_role_credentials::GetRoleCredentialsError>> {
+    client.get_role_credentials()
+        .account_id(&sso.account_id)
+        .role_name(&sso.role_name)
+        .access_token(access_token)
+        .send().await
+}
+
+/// Valid access token from cache, or from a new device authorization if needed (or forced)
+async fn get_access_token(sso: &AwsSsoConfig, client: &aws_sdk_ssooidc::Client, cache_path: &Path, force: bool)
+        -> Result<String, anyhow::Error> {
+    let _lock = SSO_LOGIN_LOCK.lock().await;
+    let now = SystemTime::now();
+
+    let cached = read_token_cache(cache_path);
+    if let Some(token) = cached.as_ref().filter(|t| !force && is_valid_token(t, sso, now)) {
+        debug!("Using cached SSO token {:?}", cache_path);
+        return Ok(token.access_token.clone());
+    }
+
+    let token = device_authorization(sso, client, cached.as_ref()).await?;
+    write_token_cache(cache_path, &token)?;
+
+    Ok(token.access_token)
+}
+
+/// Run device authorization flow, reusing cached client registration if still valid
+async fn device_authorization(sso: &AwsSsoConfig, client: &aws_sdk_ssooidc::Client, cached: Option<&SsoTokenCache>)
+        -> Result<SsoTokenCache, anyhow::Error> {
+
+    let registration = cached.and_then(|c| match (&c.client_id, &c.client_secret, &c.registration_expires_at) {
+        (Some(id), Some(secret), Some(exp)) if is_not_expired(exp, SystemTime::now()) =>
+            Some((id.clone(), secret.clone(), exp.clone())),
+        _ => None
+    });
+
+    let (client_id, client_secret, registration_expires_at) = match registration {
+        Some(r) => r,
+        None => {
+            let output = client.register_client()
+                .client_name("novops")
+                .client_type("public")
+                .send().await
+                .with_context(|| format!("Couldn't register SSO OIDC client in region {}", &sso.region))?;
+
+            let id = output.client_id()
+                .ok_or(anyhow::anyhow!("No client ID in SSO OIDC client registration response"))?;
+            let secret = output.client_secret()
+                .ok_or(anyhow::anyhow!("No client secret in SSO OIDC client registration response"))?;
+            let expires_at = DateTime::from_secs(output.client_secret_expires_at()).fmt(Format::DateTime)?;
+
+            (id.to_string(), secret.to_string(), expires_at)
+        }
+    };
+
+    let authorization = client.start_device_authorization()
+        .client_id(&client_id)
+        .client_secret(&client_secret)
+        .start_url(&sso.start_url)
+        .send().await
+        .with_context(|| format!("Couldn't start SSO device authorization for {}", &sso.start_url))?;
+
+    let device_code = authorization.device_code()
+        .ok_or(anyhow::anyhow!("No device code in SSO device authorization response"))?;
+    let verification_uri = authorization.verification_uri_complete()
+        .or(authorization.verification_uri())
+        .ok_or(anyhow::anyhow!("No verification URI in SSO device authorization response"))?;
+
+    // stdout may be used for outputs
+    eprintln!("SSO token for {} is missing or expired. Open this URL in a browser to authorize access:\n\n  {}\n\nThen check code: {}\n",
+        &sso.start_url, verification_uri, authorization.user_code().unwrap_or_default());
+
+    let deadline = SystemTime::now() + Duration::from_secs(authorization.expires_in().max(0) as u64);
+    let mut interval = Duration::from_secs(authorization.interval().max(1) as u64);
+
+    loop {
+        tokio::time::sleep(interval).await;
+
+        let result = client.create_token()
+            .client_id(&client_id)
+            .client_secret(&client_secret)
+            .grant_type(DEVICE_CODE_GRANT_TYPE)
+            .device_code(device_code)
+            .send().await;
+
+        match result {
+            Ok(token) => {
+                let access_token = token.access_token()
+                    .ok_or(anyhow::anyhow!("No access token in SSO OIDC token response"))?;
+                let expires_at = DateTime::from(SystemTime::now() + Duration::from_secs(token.expires_in().max(0) as u64))
+                    .fmt(Format::DateTime)?;
+
+                return Ok(SsoTokenCache {
+                    start_url: sso.start_url.clone(),
+                    region: sso.region.clone(),
+                    access_token: access_token.to_string(),
+                    expires_at,
+                    client_id: Some(client_id),
+                    client_secret: Some(client_secret),
+                    registration_expires_at: Some(registration_expires_at),
+                    refresh_token: token.refresh_token().map(String::from)
+                })
+            },
+            Err(e) => match e.as_service_error() {
+                Some(s) if s.is_authorization_pending_exception() => debug!("Waiting for SSO device authorization..."),
+                Some(s) if s.is_slow_down_exception() => interval += SLOW_DOWN_INCREMENT,
+                _ => return Err(anyhow::Error::from(e).context(format!("SSO device authorization failed for {}", &sso.start_url)))
+            }
+        }
+
+        if SystemTime::now() > deadline {
+            return Err(anyhow::anyhow!("SSO device authorization for {} expired before access was authorized", &sso.start_url))
+        }
+    }
+}
+
+/// Token cache path, named after SHA1 of session name or start URL as done by AWS CLI
+fn sso_token_cache_path(sso: &AwsSsoConfig) -> Result<PathBuf, anyhow::Error> {
+    let home = home::home_dir()
+        .ok_or(anyhow::anyhow!("Couldn't find home directory to locate SSO token cache"))?;
+
+    Ok(home.join(".aws").join("sso").join("cache").join(sso_token_cache_file_name(sso)))
+}
+
+fn sso_token_cache_file_name(sso: &AwsSsoConfig) -> String {
+    let key = sso.session_name.as_ref().unwrap_or(&sso.start_url);
+    format!("{}.json", hex::encode(Sha1::digest(key.as_bytes())))
+}
+
+/// Cached token if any. An unreadable cache is ignored as a new token can be requested.
+fn read_token_cache(path: &Path) -> Option<SsoTokenCache> {
+    let content = match fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(e) => {
+            debug!("No SSO token read from cache {:?}: {}", path, e);
+            return None
+        }
+    };
+
+    match serde_json::from_str(&content) {
+        Ok(t) => Some(t),
+        Err(e) => {
+            warn!("Ignoring invalid SSO token cache {:?}: {}", path, e);
+            None
+        }
+    }
+}
+
+fn write_token_cache(path: &Path, token: &SsoTokenCache) -> Result<(), anyhow::Error> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Couldn't create SSO token cache directory {:?}", dir))?;
+    }
+
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)
+        .with_context(|| format!("Couldn't open SSO token cache {:?}", path))?;
+
+    file.write_all(serde_json::to_string(token)?.as_bytes())
+        .with_context(|| format!("Couldn't write SSO token cache {:?}", path))
+}
+
+/// Whether cached token is for SSO config and not (about to be) expired
+fn is_valid_token(token: &SsoTokenCache, sso: &AwsSsoConfig, now: SystemTime) -> bool {
+    token.start_url == sso.start_url && is_not_expired(&token.expires_at, now)
+}
+
+/// Whether RFC 3339 date is later than now, with a margin. Unparseable dates are considered expired.
+fn is_not_expired(date: &str, now: SystemTime) -> bool {
+    DateTime::from_str(date, Format::DateTime).ok()
+        .and_then(|d| SystemTime::try_from(d).ok())
+        .is_some_and(|d| d > now + SSO_EXPIRY_MARGIN)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sso_config(session_name: Option<&str>) -> AwsSsoConfig {
+        AwsSsoConfig {
+            start_url: String::from("https://my-org.awsapps.com/start"),
+            region: String::from("eu-west-3"),
+            account_id: String::from("123456789012"),
+            role_name: String::from("Developer"),
+            session_name: session_name.map(String::from)
+        }
+    }
+
+    #[test]
+    fn test_sso_token_cache_file_name() {
+        assert_eq!(sso_token_cache_file_name(&sso_config(None)), "acff06c7037450e5a3fddcacb0a34e921da42d68.json");
+        assert_eq!(sso_token_cache_file_name(&sso_config(Some("my-sso"))), "0ad374308c5a4e22f723adf10145eafad7c4031c.json");
+    }
+
+    #[test]
+    fn test_sso_token_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sso").join("cache").join("token.json");
+        assert_eq!(read_token_cache(&path), None);
+
+        // as written by AWS CLI
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, r#"{"startUrl": "https://my-org.awsapps.com/start", "region": "eu-west-3",
+            "accessToken": "token", "expiresAt": "2024-06-01T12:00:00Z", "unknownField": "ignored"}"#).unwrap();
+
+        let token = read_token_cache(&path).unwrap();
+        let sso = sso_config(None);
+        let expires_at = UNIX_EPOCH + Duration::from_secs(1717243200);
+
+        assert!(is_valid_token(&token, &sso, expires_at - Duration::from_secs(3600)));
+        assert!(!is_valid_token(&token, &sso, expires_at - Duration::from_secs(30)));
+        assert!(!is_valid_token(&token, &sso, expires_at + Duration::from_secs(3600)));
+        assert!(!is_valid_token(&token, &AwsSsoConfig { start_url: String::from("https://other.awsapps.com/start"), ..sso },
+            expires_at - Duration::from_secs(3600)));
+
+        let token = SsoTokenCache { client_id: Some(String::from("client")), refresh_token: None, ..token };
+        write_token_cache(&path, &token).unwrap();
+        assert_eq!(read_token_cache(&path), Some(token));
+
+        fs::write(&path, "not json").unwrap();
+        assert_eq!(read_token_cache(&path), None);
+    }
+}
@@ -0,0 +1,88 @@
+use serde::Deserialize;
+use async_trait::async_trait;
+use anyhow::{self, Context};
+use schemars::JsonSchema;
+
+use crate::core::{ResolveTo, NovopsContext, StringResolvableInput};
+use crate::modules::variables::VariableOutput;
+use crate::modules::aws::client::build_client_without_credentials;
+use crate::modules::aws::assume_role::{resolve_role_session_name, credentials_outputs};
+
+/// Assume an IAM Role with an OpenID Connect (OIDC) token, such as a CI job token from GitLab or GitHub.
+/// 
+/// Exactly one of `web_identity_token_file`, `web_identity_token_env` or `web_identity_token` must be set.
+#[derive(Debug, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct AwsAssumeRoleWithWebIdentityInput {
+
+    /// Full IAM Role ARN
+    pub role_arn: String,
+
+    /// Path to file containing OIDC token
+    pub web_identity_token_file: Option<String>,
+
+    /// Environment variable containing OIDC token, such as `GITLAB_OIDC_TOKEN`
+    pub web_identity_token_env: Option<String>,
+
+    /// OIDC token. Can be a plain string or any Input resolving to a string. 
+    pub web_identity_token: Option<StringResolvableInput>,
+
+    /// Duration of the role session (seconds). 
+    /// Can range from 900 seconds up to the maximum session duration set for the role.
+    /// Default to 1h (3600).
+    pub duration_seconds: Option<i32>,
+
+    /// Role session name. Can be a plain string or any Input resolving to a string, 
+    /// such as `template` using `${env:NAME}` references (other variables can't be referenced).
+    /// 
+    /// Default to `novops-<app>-<environment>-<random suffix>`. 
+    pub role_session_name: Option<StringResolvableInput>,
+
+    /// Resolve this role first and use its credentials for all other AWS Inputs of environment 
+    /// (SSM parameters, Secrets Manager secrets, S3 objects...) instead of local credentials. Default: false.
+    pub use_for_inputs: Option<bool>,
+
+    /// Output credentials as this named profile in a generated AWS credentials file instead of `AWS_ACCESS_KEY_ID`, 
+    /// `AWS_SECRET_ACCESS_KEY` and `AWS_SESSION_TOKEN` variables. `AWS_SHARED_CREDENTIALS_FILE`, `AWS_CONFIG_FILE` 
+    /// and `AWS_PROFILE` variables are output instead.
+    pub credentials_profile: Option<String>,
+
+    /// Named AWS backend from `config.backends.aws` to assume role with. Default to `config.aws`.
+    pub backend: Option<String>
+}
+
+impl AwsAssumeRoleWithWebIdentityInput {
+
+    async fn web_identity_token(&self, ctx: &NovopsContext) -> Result<String, anyhow::Error> {
+        let token = match (&self.web_identity_token_file, &self.web_identity_token_env, &self.web_identity_token) {
+            (Some(path), None, None) => std::fs::read_to_string(path)
+                .with_context(|| format!("Couldn't read web identity token file {}", path))?,
+            (None, Some(var), None) => std::env::var(var)
+                .with_context(|| format!("Couldn't read web identity token from environment variable {}", var))?,
+            (None, None, Some(input)) => input.resolve(ctx).await
+                .with_context(|| "Couldn't resolve web identity token")?,
+            _ => return Err(anyhow::anyhow!("Exactly one of `web_identity_token_file`, `web_identity_token_env` \
+                or `web_identity_token` must be set to assume role {} with web identity", &self.role_arn))
+        };
+
+        Ok(token.trim().to_string())
+    }
+}
+
+#[async_trait]
+impl ResolveTo<Vec<VariableOutput>> for AwsAssumeRoleWithWebIdentityInput {
+    async fn resolve(&self, ctx: &NovopsContext) -> Result<Vec<VariableOutput>, anyhow::Error> {
+
+        let token = self.web_identity_token(ctx).await?;
+        let role_session_name = resolve_role_session_name(ctx, &self.role_arn, &self.role_session_name).await?;
+        let duration_seconds = self.duration_seconds.unwrap_or(3600);
+
+        // request is authenticated by token, local and SSO credentials are not used
+        let client = build_client_without_credentials(ctx, &self.backend).await?;
+        let assumed_role = client.assume_role_with_web_identity(&self.role_arn, &role_session_name, &token, duration_seconds).await?;
+
+        let creds = assumed_role.credentials.clone()
+            .with_context(|| format!("Can't assume role with web identity: returned Credentials Option was None for {:?}", &assumed_role))?;
+
+        Ok(credentials_outputs(&creds))
+    }
+}
@@ -1,7 +1,12 @@
 use anyhow::Context;
 use async_trait::async_trait;
 
+use std::sync::Arc;
+
 use crate::core::NovopsContext;
+use crate::clients::SharedAzureClient;
+use crate::retry::{retryable_if, is_retryable_status};
+use azure_core::error::ErrorKind;
 use azure_security_keyvault::prelude::{KeyVaultGetSecretResponse, KeyVaultGetSecretResponseAttributes};
 use azure_identity::DefaultAzureCredential;
 use azure_security_keyvault::KeyvaultClient;
@@ -12,7 +17,10 @@ pub trait AzureClient {
    async fn get_keyvault_secret(&self, vault: &str, name: &str, version: &Option<String>) -> Result<KeyVaultGetSecretResponse, anyhow::Error>;
 }
 
-pub struct DefaultAzureClient {}
+pub struct DefaultAzureClient {
+    /// Credential shared by all requests, caching tokens it obtains
+    credential: Arc<DefaultAzureCredential>
+}
 pub struct DryRunAzureClient {}
 
 #[async_trait]
@@ -21,17 +29,28 @@ impl AzureClient for DefaultAzureClient{
 
     async fn get_keyvault_secret(&self, vault: &str, name: &str, version: &Option<String>) -> Result<KeyVaultGetSecretResponse, anyhow::Error> {
 
-        let credential = DefaultAzureCredential::default();
         let url = &format!("https://{}.vault.azure.net", vault);
-        let client = KeyvaultClient::new(url, std::sync::Arc::new(credential))
+        let client = KeyvaultClient::new(url, self.credential.clone())
             .with_context(|| format!("Couldn't create Azure Vault client for {:}", url))?
             .secret_client();
 
-        let secret = client.get(name).version(version.clone().unwrap_or_default()).await?;
+        let secret = client.get(name).version(version.clone().unwrap_or_default()).await
+            .map_err(classify_azure_error)?;
         Ok(secret)
     }
 }
 
+/// Mark transient Azure errors (throttling, server or network errors) as retryable
+fn classify_azure_error(err: azure_core::error::Error) -> anyhow::Error {
+    let retryable = match err.kind() {
+        ErrorKind::HttpResponse { status, .. } => is_retryable_status(*status as u16),
+        ErrorKind::Io => true,
+        _ => false
+    };
+
+    retryable_if(err, retryable)
+}
+
 #[async_trait]
 impl AzureClient for DryRunAzureClient{
 
@@ -55,10 +74,13 @@ impl AzureClient for DryRunAzureClient{
     }
 }
 
-pub fn get_client(ctx: &NovopsContext) -> Box<dyn AzureClient + Send + Sync> {
-    if ctx.dry_run {
-        Box::new(DryRunAzureClient{})
-    } else {
-        Box::new(DefaultAzureClient{})
-    }
+pub async fn get_client(ctx: &NovopsContext) -> Result<SharedAzureClient, anyhow::Error> {
+    ctx.clients.azure(|| async {
+        let client: SharedAzureClient = if ctx.dry_run {
+            Arc::new(DryRunAzureClient{})
+        } else {
+            Arc::new(DefaultAzureClient{ credential: Arc::new(DefaultAzureCredential::default()) })
+        };
+        Ok(client)
+    }).await
 }
\ No newline at end of file
@@ -3,7 +3,9 @@ use async_trait::async_trait;
 use schemars::JsonSchema;
 use std::default::Default;
 
-use crate::core::{ResolveTo, NovopsContext};
+use crate::core::{ResolveTo, NovopsContext, StringResolvableInput};
+
+use crate::modules::json::extract_json_value;
 
 use super::client::get_client;
 
@@ -25,10 +27,22 @@ pub struct AzureKeyvaultSecret {
     pub vault: String,
     
     /// Secret name
-    pub name: String,
+    /// 
+    /// Can be a plain string or any Input resolving to a string, such as `template` or another secret.
+    pub name: Box<StringResolvableInput>,
 
     /// Secret's version (default: latest)
     pub version: Option<String>,
+
+    /// Extract value at JSON Pointer from JSON secret, such as `/password` for `{"username": "...", "password": "..."}`
+    pub extract: Option<String>,
+}
+
+impl AzureKeyvaultSecretInput {
+    /// Names of other variables this Input references, which must be resolved first
+    pub fn referenced_variables(&self) -> Result<Vec<String>, anyhow::Error> {
+        self.azure_keyvault_secret.name.referenced_variables()
+    }
 }
 
 #[async_trait]
@@ -37,10 +51,11 @@ impl ResolveTo<String> for AzureKeyvaultSecretInput {
     async fn resolve(&self, ctx: &NovopsContext) -> Result<String, anyhow::Error> {
 
         let s = self.azure_keyvault_secret.clone();
-        let client = get_client(ctx);
+        let name = s.name.resolve(ctx).await?;
+        let client = get_client(ctx).await?;
         
-        let result = client.get_keyvault_secret(&s.vault, &s.name, &s.version).await?;
-        Ok(result.value)
+        let result = client.get_keyvault_secret(&s.vault, &name, &s.version).await?;
+        extract_json_value(ctx, result.value, &s.extract)
     }
 
 }
@@ -9,6 +9,7 @@ use log::warn;
 
 use crate::core::{ResolveTo, NovopsContext, BytesResolvableInput};
 use crate::modules::variables::VariableOutput;
+use crate::retry::RetryConfig;
 
 
 #[derive(Debug, Deserialize, Clone, PartialEq, JsonSchema)]
@@ -48,7 +49,19 @@ pub struct FileInput {
     pub variable: Option<String>,
     
     /// File content 
-    pub content: BytesResolvableInput
+    pub content: BytesResolvableInput,
+
+    /// Retry policy for this file, overriding `config.default.retry`
+    pub retry: Option<RetryConfig>,
+
+    /// Timeout in seconds for each attempt to resolve this file, overriding `config.default.timeout`
+    pub timeout: Option<u64>,
+
+    /// If true, file is not generated when its content can't be resolved, failure being logged as a warning. Default: false.
+    pub optional: Option<bool>,
+
+    /// Content to use when file content can't be resolved, failure being logged as a warning
+    pub default: Option<String>
 }
 
 
@@ -0,0 +1,57 @@
+use serde::Deserialize;
+use async_trait::async_trait;
+use anyhow;
+use schemars::JsonSchema;
+use log::warn;
+
+use crate::core::{ResolveTo, NovopsContext, StringResolvableInput};
+use crate::retry::{is_retryable, retryable_if};
+
+/// Try Inputs in order and use the first one resolving successfully
+#[derive(Debug, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct FirstOfInput {
+    /// Inputs to try in order, such as a remote secret followed by a local fallback
+    pub first_of: Vec<StringResolvableInput>
+}
+
+impl FirstOfInput {
+    /// Names of other variables referenced by any of the Inputs
+    pub fn referenced_variables(&self) -> Result<Vec<String>, anyhow::Error> {
+        let mut result = vec![];
+        for input in &self.first_of {
+            result.extend(input.referenced_variables()?);
+        }
+        Ok(result)
+    }
+}
+
+#[async_trait]
+impl ResolveTo<String> for FirstOfInput {
+    async fn resolve(&self, ctx: &NovopsContext) -> Result<String, anyhow::Error> {
+
+        if self.first_of.is_empty() {
+            return Err(anyhow::anyhow!("first_of requires at least one Input"));
+        }
+
+        let mut errors = vec![];
+        for (i, input) in self.first_of.iter().enumerate() {
+            match input.resolve(ctx).await {
+                Ok(r) => return Ok(r),
+                Err(e) => {
+                    warn!("first_of Input #{} failed to resolve, trying next Input: {:#}", i, e);
+                    errors.push(e);
+                }
+            }
+        }
+
+        // may succeed if retried as long as one of the Inputs failed with a transient error
+        let retryable = errors.iter().any(is_retryable);
+
+        let mut message = String::from("None of first_of Inputs could be resolved:\n");
+        for (i, err) in errors.iter().enumerate() {
+            message.push_str(format!("\n--- Input #{}\n{:?}\n", i, err).as_str());
+        }
+
+        Err(retryable_if(anyhow::anyhow!(message), retryable))
+    }
+}
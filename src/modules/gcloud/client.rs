@@ -18,8 +18,12 @@ use log::debug;
 use async_trait::async_trait;
 use home;
 use std::env;
+use std::sync::Arc;
+use tokio::sync::OnceCell;
 
 use crate::core::NovopsContext;
+use crate::clients::SharedGCloudClient;
+use crate::retry::{retryable_if, is_retryable_status};
 
 
 #[async_trait]
@@ -27,7 +31,10 @@ pub trait GCloudClient {
    async fn get_secret_version(&self, name: &str) -> Result<SecretPayload, anyhow::Error>;
 }
 
-pub struct DefaultGCloudClient {}
+pub struct DefaultGCloudClient {
+    /// Authenticator created on first use, shared by all requests
+    authenticator: OnceCell<Authenticator<HttpsConnector<HttpConnector>>>
+}
 pub struct DryRunGCloudClient {}
 
 #[async_trait]
@@ -36,8 +43,9 @@ impl GCloudClient for DefaultGCloudClient{
     async fn get_secret_version(&self, name: &str) -> Result<SecretPayload, anyhow::Error> {
         debug!("Retrieving secret: {:}", &name);
 
-        let authenticator = get_authenticator()
-            .await.with_context(|| "Couldn't get Google client authenticator")?;
+        let authenticator = self.authenticator.get_or_try_init(get_authenticator)
+            .await.with_context(|| "Couldn't get Google client authenticator")?
+            .clone();
 
         let hub = SecretManager::new(
             hyper::Client::builder().build(
@@ -49,7 +57,8 @@ impl GCloudClient for DefaultGCloudClient{
         let (_, secret) = hub.projects()
             .secrets_versions_access(name)
             .doit()
-            .await.with_context(|| format!("Couldn't get secret {:?}. Did you setup credentials compatible with Application Default Credentials?", name))?;
+            .await.map_err(classify_gcloud_error)
+            .with_context(|| format!("Couldn't get secret {:?}. Did you setup credentials compatible with Application Default Credentials?", name))?;
 
         let result = secret.payload
             .ok_or(anyhow::anyhow!("No secret value found for '{}'", name))?;
@@ -59,6 +68,17 @@ impl GCloudClient for DefaultGCloudClient{
     }
 }
 
+/// Mark transient Google Cloud errors (throttling, server or network errors) as retryable
+fn classify_gcloud_error(err: google_secretmanager1::Error) -> anyhow::Error {
+    let retryable = match &err {
+        google_secretmanager1::Error::Failure(response) => is_retryable_status(response.status().as_u16()),
+        google_secretmanager1::Error::HttpError(_) | google_secretmanager1::Error::Io(_) => true,
+        _ => false
+    };
+
+    retryable_if(err, retryable)
+}
+
 /// google_secretmanager1 uses yup_oauth2 which provides a bogus Application Default Credentials workflow
 ///
 /// Google Application Default Credentials is documented as:
@@ -210,10 +230,13 @@ impl GCloudClient for DryRunGCloudClient{
     }
 }
 
-pub async fn get_client(ctx: &NovopsContext) -> Box<dyn GCloudClient + Send + Sync> {
-    if ctx.dry_run {
-        Box::new(DryRunGCloudClient{})
-    } else {
-        Box::new(DefaultGCloudClient{})
-    }
+pub async fn get_client(ctx: &NovopsContext) -> Result<SharedGCloudClient, anyhow::Error> {
+    ctx.clients.gcloud(|| async {
+        let client: SharedGCloudClient = if ctx.dry_run {
+            Arc::new(DryRunGCloudClient{})
+        } else {
+            Arc::new(DefaultGCloudClient{ authenticator: OnceCell::new() })
+        };
+        Ok(client)
+    }).await
 }
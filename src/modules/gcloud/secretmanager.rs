@@ -7,7 +7,8 @@ use crc32c;
 use log::debug;
 
 
-use crate::core::{ResolveTo, NovopsContext};
+use crate::core::{ResolveTo, NovopsContext, StringResolvableInput};
+use crate::modules::json::{extract_json_value, extract_json_bytes};
 use super::client::get_client;
 
 /// Reference Secret Manager secret
@@ -27,10 +28,22 @@ pub struct GCloudSecretManagerSecret {
     /// Name of the secret in the format projects/\*\/secrets/\*\/versions/\*
     /// Such as `projects/my-org-project/secrets/my-secret/latest`
     /// Or `projects/my-org-project/secrets/my-secret/42` for a specific version
-    pub name: String,
+    /// 
+    /// Can be a plain string or any Input resolving to a string, such as `template` or another secret.
+    pub name: Box<StringResolvableInput>,
 
     /// Whether to validate crc32c checksum provided with secret (default: true)
-    pub validate_crc32c: Option<bool>
+    pub validate_crc32c: Option<bool>,
+
+    /// Extract value at JSON Pointer from JSON secret, such as `/password` for `{"username": "...", "password": "..."}`
+    pub extract: Option<String>
+}
+
+impl GCloudSecretManagerSecretInput {
+    /// Names of other variables this Input references, which must be resolved first
+    pub fn referenced_variables(&self) -> Result<Vec<String>, anyhow::Error> {
+        self.gcloud_secret.name.referenced_variables()
+    }
 }
 
 #[async_trait]
@@ -40,9 +53,9 @@ impl ResolveTo<String> for GCloudSecretManagerSecretInput {
         let value = retrieve_secret_bytes_for(ctx, &self.gcloud_secret).await?;
 
         let result = String::from_utf8(value)
-            .with_context(|| format!("Couldn't convert secret {:} bytes into String", &self.gcloud_secret.name))?;
+            .with_context(|| "Couldn't convert secret bytes into String")?;
 
-        return Ok(result);
+        return extract_json_value(ctx, result, &self.gcloud_secret.extract);
 
     }
 }
@@ -50,7 +63,9 @@ impl ResolveTo<String> for GCloudSecretManagerSecretInput {
 #[async_trait]
 impl ResolveTo<Vec<u8>> for GCloudSecretManagerSecretInput {
     async fn resolve(&self, ctx: &NovopsContext) -> Result<Vec<u8>, anyhow::Error> {
-        return retrieve_secret_bytes_for(ctx, &self.gcloud_secret).await;
+        let value = retrieve_secret_bytes_for(ctx, &self.gcloud_secret).await?;
+
+        return extract_json_bytes(ctx, value, &self.gcloud_secret.extract);
     }
 }
 
@@ -58,25 +73,27 @@ impl ResolveTo<Vec<u8>> for GCloudSecretManagerSecretInput {
 /// Return bytes value or a secret after validating CRC32
 async fn retrieve_secret_bytes_for(ctx: &NovopsContext, secret: &GCloudSecretManagerSecret) -> Result<Vec<u8>, anyhow::Error> {
     
-    let client = get_client(ctx).await;
+    let name = secret.name.resolve(ctx).await?;
+
+    let client = get_client(ctx).await?;
 
-    let payload = client.get_secret_version(&secret.name).await?;
+    let payload = client.get_secret_version(&name).await?;
 
     let payload_data = payload.data
-        .ok_or(anyhow::anyhow!("No payload data obtained for {}", &secret.name))?;
+        .ok_or(anyhow::anyhow!("No payload data obtained for {}", &name))?;
 
     if secret.validate_crc32c.unwrap_or(true) {
         let expected_checksum = payload.data_crc32c
-            .ok_or(anyhow::anyhow!("No CRC32C found for {:}. If the secret has no CRC32C, set 'validate_crc32c: false' on input", &secret.name))?;
+            .ok_or(anyhow::anyhow!("No CRC32C found for {:}. If the secret has no CRC32C, set 'validate_crc32c: false' on input", &name))?;
 
         let calculated_checksum = i64::from(crc32c::crc32c(&payload_data));
 
         debug!("Secret {:} - expected checksum: {:} - calculated checksum: {:}", 
-            &secret.name, &expected_checksum, &calculated_checksum);
+            &name, &expected_checksum, &calculated_checksum);
 
         anyhow::ensure!(expected_checksum == calculated_checksum, 
             format!("Couldn't validate checksum for {:}: expected '{:}' got '{:}'", 
-            &secret.name, expected_checksum, calculated_checksum));
+            &name, expected_checksum, calculated_checksum));
     }
     
     Ok(payload_data)
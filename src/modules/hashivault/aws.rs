@@ -6,6 +6,7 @@ use anyhow::Context;
 use serde::Deserialize;
 use async_trait::async_trait;
 use schemars::JsonSchema;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 /// Reference AWS Secret Engine role to generate AWS credentials as environment variables.
 /// 
@@ -26,14 +27,26 @@ pub struct HashiVaultAWSInput {
     pub role_session_name: Option<String>,
     
     /// Generated token time to live. Example: "3600s"
-    pub ttl: Option<String>
+    pub ttl: Option<String>,
+
+    /// Named Vault backend from `config.backends.hashivault` to use. Default to `config.hashivault`.
+    pub backend: Option<String>,
+
+    /// Generate credentials first and use them for all AWS Inputs of environment 
+    /// (SSM parameters, Secrets Manager secrets, S3 objects...) instead of local credentials. Default: false.
+    pub use_for_inputs: Option<bool>,
+
+    /// Output credentials as this named profile in a generated AWS credentials file instead of `AWS_ACCESS_KEY_ID`, 
+    /// `AWS_SECRET_ACCESS_KEY` and `AWS_SESSION_TOKEN` variables. `AWS_SHARED_CREDENTIALS_FILE`, `AWS_CONFIG_FILE` 
+    /// and `AWS_PROFILE` variables are output instead.
+    pub credentials_profile: Option<String>
 }
 
 #[async_trait]
 impl ResolveTo<Vec<VariableOutput>> for HashiVaultAWSInput {
   async fn resolve(&self, ctx: &NovopsContext) -> Result<Vec<VariableOutput>, anyhow::Error> {
     
-    let client = get_client(ctx).await?;
+    let client = get_client(ctx, &self.backend).await?;
 
     let creds = client.aws_creds(
       &Some(self.mount.clone().unwrap_or("aws".to_string())), 
@@ -53,6 +66,12 @@ impl ResolveTo<Vec<VariableOutput>> for HashiVaultAWSInput {
       result.push(VariableOutput{ name: "AWS_SESSION_TOKEN".to_string(), value: creds.security_token.unwrap() })
     }
 
+    // Vault lease gives credentials expiration, unless credentials don't expire
+    if creds.lease_duration > 0 {
+      let expiration = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() + u64::from(creds.lease_duration);
+      result.push(VariableOutput{ name: "AWS_SESSION_EXPIRATION".to_string(), value: expiration.to_string() })
+    }
+
     Ok(result)
   }
 }
@@ -1,4 +1,9 @@
-use crate::core::NovopsContext;
+use std::sync::Arc;
+
+use crate::core::{NovopsContext, sorted_keys};
+use crate::clients::{SharedHashivaultClient, OnceMap};
+use crate::retry::{retryable_if, is_retryable_status};
+use rustify::errors::ClientError as RestClientError;
 
 use anyhow::{Context, Error};
 use vaultrs::client::{Client, VaultClient, VaultClientSettingsBuilder};
@@ -6,7 +11,10 @@ use url::Url;
 use async_trait::async_trait;
 use std::{ collections::HashMap, env, fs, path::{Path, PathBuf}, time::Duration };
 use std::env::VarError;
-use vaultrs::{kv2, kv1, aws, auth, api::aws::requests::GenerateCredentialsRequest};
+use vaultrs::{kv2, kv1, auth, api::EndpointResult};
+use vaultrs::api::aws::{requests::GenerateCredentialsRequest, responses::GenerateCredentialsResponse};
+use rustify::endpoint::Endpoint;
+use vaultrs::error::ClientError;
 use log::debug;
 use home;
 use crate::modules::hashivault::config::HashiVaultAuth;
@@ -28,11 +36,26 @@ const CLIENT_TIMEOUT_DEFAULT : u64 = 60;
 
 #[async_trait]
 pub trait HashivaultClient {
+    /// Read key of a KV v2 secret, latest version unless `version` is set
     async fn kv2_read(&self, 
         mount: &Option<String>, 
         path: &str, 
-        key: &str
+        key: &str,
+        version: Option<u64>
     ) -> Result<String, anyhow::Error>;
+
+    /// Read all keys of a KV v2 secret, latest version unless `version` is set
+    async fn kv2_read_all(&self, 
+        mount: &Option<String>, 
+        path: &str, 
+        version: Option<u64>
+    ) -> Result<HashMap<String, String>, anyhow::Error>;
+
+    /// List secrets under a KV v2 folder. Nested folder names end with `/`.
+    async fn kv2_list(&self, 
+        mount: &Option<String>, 
+        path: &str
+    ) -> Result<Vec<String>, anyhow::Error>;
     
     async fn kv1_read(&self, 
         mount: &Option<String>, 
@@ -50,7 +73,13 @@ pub trait HashivaultClient {
 }
 
 pub struct DefaultHashivaultClient{
-    client: VaultClient
+    client: VaultClient,
+
+    /// KV v2 secrets already read by mount, path and version, so that each secret is read once for all its keys
+    kv2_secrets: OnceMap<(String, String, Option<u64>), HashMap<String, String>>,
+
+    /// KV v1 secrets already read by mount and path
+    kv1_secrets: OnceMap<(String, String), HashMap<String, String>>
 }
 
 pub struct DryRunHashivaultClient{}
@@ -59,33 +88,53 @@ pub struct Creds{
     pub access_key: String,
     pub secret_key: String,
     pub security_token: Option<String>,
-    pub arn: String
+    pub arn: String,
+
+    /// Lease duration in seconds. 0 if credentials don't expire, such as IAM user credentials.
+    pub lease_duration: u32
 }
 
 #[async_trait]
 impl HashivaultClient for DefaultHashivaultClient {
-    async fn kv2_read(&self, mount: &Option<String>, path: &str, key: &str) -> Result<String, anyhow::Error>{
+    async fn kv2_read(&self, mount: &Option<String>, path: &str, key: &str, version: Option<u64>) -> Result<String, anyhow::Error>{
+        let secret_data = self.kv2_read_all(mount, path, version).await?;
+
+        return secret_data.get(key)
+            .ok_or_else(|| anyhow::anyhow!("Mount '{:}' secret '{:}' (version: {:?}) found but key '{:}' did not exist", 
+                &mount.clone().unwrap_or("secret".to_string()), &path, &version, &key))
+            .cloned()
+    }
+
+    async fn kv2_read_all(&self, mount: &Option<String>, path: &str, version: Option<u64>) -> Result<HashMap<String, String>, anyhow::Error>{
 
         // retrieve secret using "secret" mount by default
         let _mount = mount.clone().unwrap_or("secret".to_string());
-        let secret_data: HashMap<String, String> = kv2::read(
-            &self.client, 
-            &_mount, 
-            path
-        ).await.with_context(|| format!("Error reading '{:}' mount at path '{:}'", &_mount, &path))?;
+        self.kv2_secrets.get_or_try_init((_mount.clone(), path.to_string(), version), || async {
+            match version {
+                Some(v) => kv2::read_version(&self.client, &_mount, path, v).await,
+                None => kv2::read(&self.client, &_mount, path).await
+            }.map_err(classify_vault_error)
+            .with_context(|| format!("Error reading '{:}' mount at path '{:}' (version: {:?})", &_mount, &path, &version))
+        }).await
+    }
 
-        return secret_data.get(key)
-            .ok_or_else(|| anyhow::anyhow!("Mount '{:}' secret '{:}' found but key '{:}' did not exist", &_mount, &path, &key))
-            .cloned()
+    async fn kv2_list(&self, mount: &Option<String>, path: &str) -> Result<Vec<String>, anyhow::Error>{
+        let _mount = mount.clone().unwrap_or("secret".to_string());
+        kv2::list(&self.client, &_mount, path).await
+            .map_err(classify_vault_error)
+            .with_context(|| format!("Error listing '{:}' mount at path '{:}'", &_mount, &path))
     }
 
     async fn kv1_read(&self, mount: &Option<String>, path: &str, key: &str) -> Result<String, anyhow::Error> {
         let _mount = mount.clone().unwrap_or("secret".to_string());
-        let secret_data: HashMap<String, String> = kv1::get(
-            &self.client, 
-            mount.clone().unwrap_or("secret".to_string()).as_str(), 
-            path
-        ).await.with_context(|| format!("Error reading '{:}' mount at path '{:}'", &_mount, &path))?;
+        let secret_data: HashMap<String, String> = self.kv1_secrets.get_or_try_init((_mount.clone(), path.to_string()), || async {
+            kv1::get(
+                &self.client, 
+                &_mount, 
+                path
+            ).await.map_err(classify_vault_error)
+            .with_context(|| format!("Error reading '{:}' mount at path '{:}'", &_mount, &path))
+        }).await?;
 
         return secret_data.get(key)
             .ok_or_else(|| anyhow::anyhow!("Mount '{:}' secret '{:}', found but key '{:}' did not exist", &_mount, &path, &key))
@@ -110,17 +159,31 @@ impl HashivaultClient for DefaultHashivaultClient {
             opts.ttl(ttl.clone().unwrap().to_string());
         }
 
-        let result = aws::roles::credentials(&self.client, 
-            &mount.clone().unwrap_or("aws".to_string()), 
-            role, 
-            Some(&mut opts)
-        ).await.with_context(|| format!("Couldn't generate Hashivault AWS creds for {:}", role))?;
+        let endpoint = opts
+            .mount(mount.clone().unwrap_or("aws".to_string()))
+            .name(role)
+            .build()
+            .with_context(|| format!("Couldn't build Hashivault AWS creds request for {:}", role))?;
+
+        // Call endpoint directly rather than with aws::roles::credentials which drops lease duration
+        let result = endpoint
+            .with_middleware(self.client.middle())
+            .exec(self.client.http())
+            .await
+            .map_err(|source| ClientError::RestClientError { source })
+            .and_then(|r| r.wrap::<EndpointResult<GenerateCredentialsResponse>>().map_err(ClientError::from))
+            .map_err(classify_vault_error)
+            .with_context(|| format!("Couldn't generate Hashivault AWS creds for {:}", role))?;
+
+        let creds = result.data
+            .ok_or(anyhow::anyhow!("Hashivault AWS creds response for {:} has no data", role))?;
 
         Ok(Creds {
-            access_key: result.access_key,
-            secret_key: result.secret_key,
-            security_token: result.security_token,
-            arn: result.arn
+            access_key: creds.access_key,
+            secret_key: creds.secret_key,
+            security_token: creds.security_token,
+            arn: creds.arn,
+            lease_duration: result.lease_duration
         })
 
     }
@@ -130,7 +193,7 @@ impl HashivaultClient for DefaultHashivaultClient {
 
 #[async_trait]
 impl HashivaultClient for DryRunHashivaultClient {
-    async fn kv2_read(&self, _mount: &Option<String>, path: &str, key: &str) -> Result<String, anyhow::Error>{
+    async fn kv2_read(&self, _mount: &Option<String>, path: &str, key: &str, _version: Option<u64>) -> Result<String, anyhow::Error>{
 
         let mut result = "RESULT:".to_string();
         result.push_str(format!("{:}/{:}", path, key).as_str());
@@ -138,6 +201,16 @@ impl HashivaultClient for DryRunHashivaultClient {
         Ok(result)
     }
 
+    async fn kv2_read_all(&self, _mount: &Option<String>, path: &str, _version: Option<u64>) -> Result<HashMap<String, String>, anyhow::Error>{
+        Ok(HashMap::from([
+            ("dryrun_key".to_string(), format!("RESULT:{:}/dryrun_key", path))
+        ]))
+    }
+
+    async fn kv2_list(&self, _mount: &Option<String>, _path: &str) -> Result<Vec<String>, anyhow::Error>{
+        Ok(vec!["dryrun_secret".to_string(), "dryrun_folder/".to_string()])
+    }
+
     async fn kv1_read(&self, _mount: &Option<String>, path: &str, key: &str) -> Result<String, anyhow::Error>{
 
         let mut result = "RESULT:".to_string();
@@ -159,7 +232,8 @@ impl HashivaultClient for DryRunHashivaultClient {
             access_key: "AKIADRYRUNACCESSKEY".to_string(),
             secret_key: "s3cret".to_string(),
             security_token: Some("securityToken".to_string()),
-            arn: session_arn
+            arn: session_arn,
+            lease_duration: 3600
         };
 
         Ok(result)
@@ -167,23 +241,65 @@ impl HashivaultClient for DryRunHashivaultClient {
 }
 
 
-pub async fn get_client(ctx: &NovopsContext) -> Result<Box<dyn HashivaultClient + Send + Sync>, anyhow::Error> {
+/**
+ * Get Hashivault client for backend, using default Hashivault config if no backend is specified
+ */
+pub async fn get_client(ctx: &NovopsContext, backend: &Option<String>) -> Result<SharedHashivaultClient, anyhow::Error> {
+    ctx.clients.hashivault(backend, || build_backend_client(ctx, backend)).await
+}
+
+/**
+ * Build a new Hashivault client for backend. Prefer get_client to share client with other Inputs.
+ */
+async fn build_backend_client(ctx: &NovopsContext, backend: &Option<String>) -> Result<SharedHashivaultClient, anyhow::Error> {
+    let hv_config = get_backend_config(ctx, backend)?;
+
     if ctx.dry_run {
-        Ok(Box::new(DryRunHashivaultClient{}))
+        Ok(Arc::new(DryRunHashivaultClient{}))
     } else {
-        let hv_config = ctx.config_file_data
-            .config.clone().unwrap_or_default()
-            .hashivault.unwrap_or_default();
-        
         let client = build_client(&hv_config).await
             .with_context(|| "Couldn't build Hashivault client")?;
-        Ok(Box::new(DefaultHashivaultClient{
-            client
+        Ok(Arc::new(DefaultHashivaultClient{
+            client,
+            kv2_secrets: OnceMap::default(),
+            kv1_secrets: OnceMap::default()
         }))
     }
     
 }
 
+/// Mark transient Vault errors (throttling, server or network errors) as retryable
+fn classify_vault_error(err: ClientError) -> anyhow::Error {
+    let retryable = match &err {
+        ClientError::APIError { code, .. } => is_retryable_status(*code),
+        ClientError::RestClientError { source } => match source {
+            RestClientError::ServerResponseError { code, .. } => is_retryable_status(*code),
+            RestClientError::RequestError { .. } | RestClientError::ResponseError { .. } => true,
+            _ => false
+        },
+        _ => false
+    };
+
+    retryable_if(err, retryable)
+}
+
+/**
+ * Hashivault config for backend: named backend from `config.backends.hashivault` or default `config.hashivault`
+ */
+pub fn get_backend_config(ctx: &NovopsContext, backend: &Option<String>) -> Result<HashivaultConfig, anyhow::Error> {
+    match backend {
+        Some(name) => {
+            let backends = ctx.config.backends.clone().unwrap_or_default()
+                .hashivault.unwrap_or_default();
+
+            backends.get(name).cloned()
+                .ok_or(anyhow::anyhow!("Hashivault backend '{}' not found in config. Available backends: {:?}", 
+                    name, sorted_keys(&backends)))
+        },
+        None => Ok(ctx.config.hashivault.clone().unwrap_or_default())
+    }
+}
+
 pub async fn build_client(hv_config: &HashivaultConfig) -> Result<VaultClient, anyhow::Error> {
     let default_settings = VaultClientSettingsBuilder::default().build()?;
 
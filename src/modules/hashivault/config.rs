@@ -64,4 +64,19 @@ pub struct HashivaultConfig {
   pub namespace: Option<String>
 }
 
+impl HashivaultConfig {
+  /// Merge `other` on top of this config, fields set in `other` taking precedence
+  pub fn merge(self, other: HashivaultConfig) -> HashivaultConfig {
+    HashivaultConfig {
+      address: other.address.or(self.address),
+      token: other.token.or(self.token),
+      token_path: other.token_path.or(self.token_path),
+      verify: other.verify.or(self.verify),
+      timeout: other.timeout.or(self.timeout),
+      auth: other.auth.or(self.auth),
+      namespace: other.namespace.or(self.namespace),
+    }
+  }
+}
+
 
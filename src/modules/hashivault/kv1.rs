@@ -1,4 +1,4 @@
-use crate::core::{ResolveTo, NovopsContext};
+use crate::core::{ResolveTo, NovopsContext, StringResolvableInput};
 
 use serde::Deserialize;
 use async_trait::async_trait;
@@ -20,21 +20,35 @@ pub struct HashiVaultKeyValueV1 {
     pub mount: Option<String>,
 
     /// Path to secret
-    pub path: String,
+    /// 
+    /// Can be a plain string or any Input resolving to a string, such as `template` or another secret.
+    pub path: Box<StringResolvableInput>,
 
     /// Secret key to retrieve
-    pub key: String
+    pub key: String,
+
+    /// Named Vault backend from `config.backends.hashivault` to use. Default to `config.hashivault`.
+    pub backend: Option<String>
+}
+
+impl HashiVaultKeyValueV1Input {
+  /// Names of other variables this Input references, which must be resolved first
+  pub fn referenced_variables(&self) -> Result<Vec<String>, anyhow::Error> {
+    self.hvault_kv1.path.referenced_variables()
+  }
 }
 
 #[async_trait]
 impl ResolveTo<String> for HashiVaultKeyValueV1Input {
   async fn resolve(&self, ctx: &NovopsContext) -> Result<String, anyhow::Error> {
     
-    let client = get_client(ctx).await?;
     let kv1 = &self.hvault_kv1;
+    let path = kv1.path.resolve(ctx).await?;
+
+    let client = get_client(ctx, &kv1.backend).await?;
 
     // retrieve secret using "secret" mount by default
-    let result = client.kv1_read(&kv1.mount, &kv1.path, &kv1.key).await?;
+    let result = client.kv1_read(&kv1.mount, &path, &kv1.key).await?;
     Ok(result)
   }
 }
\ No newline at end of file
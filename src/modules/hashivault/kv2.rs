@@ -1,4 +1,7 @@
-use crate::core::{ResolveTo, NovopsContext};
+use std::collections::HashSet;
+
+use crate::core::{ResolveTo, NovopsContext, StringResolvableInput};
+use crate::modules::variables::{VariableOutput, upper_snake_case};
 use super::client::get_client;
 
 use serde::Deserialize;
@@ -20,23 +23,138 @@ pub struct HashiVaultKeyValueV2 {
     pub mount: Option<String>,
 
     /// Path to secret
-    pub path: String,
+    /// 
+    /// Can be a plain string or any Input resolving to a string, such as `template` or another secret.
+    pub path: Box<StringResolvableInput>,
 
     /// Secret key to retrieve
-    pub key: String
+    pub key: String,
+
+    /// Secret version to read. Default to latest version.
+    pub version: Option<u64>,
+
+    /// Named Vault backend from `config.backends.hashivault` to use. Default to `config.hashivault`.
+    pub backend: Option<String>
+}
+
+impl HashiVaultKeyValueV2Input {
+  /// Names of other variables this Input references, which must be resolved first
+  pub fn referenced_variables(&self) -> Result<Vec<String>, anyhow::Error> {
+    self.hvault_kv2.path.referenced_variables()
+  }
 }
 
 #[async_trait]
 impl ResolveTo<String> for HashiVaultKeyValueV2Input {
   async fn resolve(&self, ctx: &NovopsContext) -> Result<String, anyhow::Error> {
     
-    let client = get_client(ctx).await?;
+    let path = self.hvault_kv2.path.resolve(ctx).await?;
+
+    let client = get_client(ctx, &self.hvault_kv2.backend).await?;
     let result = client.kv2_read(
         &self.hvault_kv2.mount, 
-        &self.hvault_kv2.path, 
-        &self.hvault_kv2.key
+        &path, 
+        &self.hvault_kv2.key,
+        self.hvault_kv2.version
     ).await?;
 
     Ok(result)
   }
 }
+
+/// Load all keys of a Key Value V2 secret, or of every secret in a folder, as variables
+#[derive(Debug, Deserialize, Clone, PartialEq, JsonSchema, Default)]
+pub struct HashiVaultKeyValueV2AllInput {
+    /// KV v2 mount point
+    /// 
+    /// default to "secret/"
+    pub mount: Option<String>,
+
+    /// Path to secret, or to folder if `list` is set
+    pub path: String,
+
+    /// List secrets under `path` folder (`LIST` request) and load all keys of each of them. 
+    /// Nested folders are ignored. Default: false
+    pub list: Option<bool>,
+
+    /// Secret version to read. Default to latest version. Can't be used with `list`.
+    pub version: Option<u64>,
+
+    /// Variable name template: `{key}` is replaced by secret key and `{secret}` by secret name (last element of its path), 
+    /// such as `APP_{key}`. Default: `{key}`, or `{secret}_{key}` with `list`
+    pub name: Option<String>,
+
+    /// Convert variable names to upper snake case, such as `db-password` to `DB_PASSWORD`: 
+    /// characters other than letters and digits are replaced by `_`. Default: true
+    pub upper_snake_case: Option<bool>,
+
+    /// Named Vault backend from `config.backends.hashivault` to use. Default to `config.hashivault`.
+    pub backend: Option<String>
+}
+
+impl HashiVaultKeyValueV2AllInput {
+
+  /// Variable name for secret key following name template
+  fn variable_name(&self, secret_path: &str, key: &str) -> String {
+    let default_template = if self.list.unwrap_or(false) { "{secret}_{key}" } else { "{key}" };
+    let secret = secret_path.trim_end_matches('/').rsplit('/').next().unwrap_or(secret_path);
+
+    let name = self.name.as_deref().unwrap_or(default_template)
+      .replace("{secret}", secret)
+      .replace("{key}", key);
+
+    if self.upper_snake_case.unwrap_or(true) {
+      upper_snake_case(&name)
+    } else {
+      name
+    }
+  }
+}
+
+#[async_trait]
+impl ResolveTo<Vec<VariableOutput>> for HashiVaultKeyValueV2AllInput {
+  async fn resolve(&self, ctx: &NovopsContext) -> Result<Vec<VariableOutput>, anyhow::Error> {
+
+    let client = get_client(ctx, &self.backend).await?;
+
+    let secret_paths = if self.list.unwrap_or(false) {
+      if self.version.is_some() {
+        return Err(anyhow::anyhow!("`version` can't be used with `list` for folder {}", &self.path));
+      }
+
+      let folder = self.path.trim_end_matches('/');
+      client.kv2_list(&self.mount, folder).await?.into_iter()
+        .filter(|s| !s.ends_with('/'))
+        .map(|s| format!("{}/{}", folder, s))
+        .collect()
+    } else {
+      vec![self.path.clone()]
+    };
+
+    let mut names = HashSet::new();
+    let mut variables = vec![];
+    for secret_path in secret_paths {
+      let data = client.kv2_read_all(&self.mount, &secret_path, self.version).await?;
+
+      let mut keys: Vec<&String> = data.keys().collect();
+      keys.sort();
+
+      for key in keys {
+        let name = self.variable_name(&secret_path, key);
+        if name.is_empty() {
+          return Err(anyhow::anyhow!("Key {} of secret {} resolved to an empty variable name. Use `name` to set a name template.", 
+            key, &secret_path));
+        }
+
+        if !names.insert(name.clone()) {
+          return Err(anyhow::anyhow!("Several keys under path {} resolved to variable name {}. Use `name` to set a distinct name template.", 
+            &self.path, name));
+        }
+
+        variables.push(VariableOutput { name, value: data[key].clone() });
+      }
+    }
+
+    Ok(variables)
+  }
+}
@@ -0,0 +1,208 @@
+use std::collections::{HashMap, HashSet};
+use serde::Deserialize;
+use serde_json::Value;
+use async_trait::async_trait;
+use anyhow::{self, Context};
+use schemars::JsonSchema;
+
+use crate::core::{ResolveTo, NovopsContext, StringResolvableInput};
+use crate::modules::variables::{VariableOutput, upper_snake_case};
+
+/// Expand all keys of a JSON object secret into variables
+#[derive(Debug, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct ExpandJsonInput {
+
+    /// JSON object secret, such as an `aws_secret` or `hvault_kv2` Input resolving to `{"username": "...", "password": "..."}`.
+    ///
+    /// Other variables of environment can't be referenced.
+    pub value: StringResolvableInput,
+
+    /// Expand only object at this JSON Pointer, such as `/database`
+    pub extract: Option<String>,
+
+    /// Prefix added to variable names, such as `DB_`
+    pub prefix: Option<String>,
+
+    /// Convert keys to upper snake case variable names, such as `api-key` to `API_KEY`:
+    /// characters other than letters and digits are replaced by `_`. Default: true
+    pub upper_snake_case: Option<bool>,
+
+    /// Separator joining keys of nested objects, such as `database.host` with `.`. Default: `_`
+    pub separator: Option<String>,
+
+    /// Explicit variable names by key (nested keys joined by `separator`), taking precedence over other name rules.
+    pub rename: Option<HashMap<String, String>>
+}
+
+impl ExpandJsonInput {
+
+    /// Variable name for key following name rules
+    fn variable_name(&self, key: &str) -> String {
+        if let Some(name) = self.rename.as_ref().and_then(|r| r.get(key)) {
+            return name.clone();
+        }
+
+        let name = if self.upper_snake_case.unwrap_or(true) {
+            upper_snake_case(key)
+        } else {
+            key.to_string()
+        };
+
+        format!("{}{}", self.prefix.clone().unwrap_or_default(), name)
+    }
+}
+
+#[async_trait]
+impl ResolveTo<Vec<VariableOutput>> for ExpandJsonInput {
+    async fn resolve(&self, ctx: &NovopsContext) -> Result<Vec<VariableOutput>, anyhow::Error> {
+
+        let content = self.value.resolve(ctx).await?;
+
+        // dummy secrets aren't JSON
+        if ctx.dry_run && serde_json::from_str::<Value>(&content).is_err() {
+            return Ok(vec![VariableOutput { name: self.variable_name("result"), value: content }]);
+        }
+
+        let json: Value = serde_json::from_str(&content)
+            .with_context(|| "Couldn't parse secret as JSON")?;
+
+        let json = match &self.extract {
+            Some(pointer) => json_pointer(&json, pointer)?,
+            None => &json
+        };
+
+        let separator = self.separator.clone().unwrap_or(String::from("_"));
+        let mut entries = vec![];
+        flatten_json_object(json, "", &separator, &mut entries)?;
+
+        let mut names = HashSet::new();
+        let mut variables = vec![];
+        for (key, value) in entries {
+            let name = self.variable_name(&key);
+
+            if !names.insert(name.clone()) {
+                return Err(anyhow::anyhow!("Several JSON keys resolved to variable name {}. Use `rename` to set distinct names.", name));
+            }
+
+            variables.push(VariableOutput { name, value });
+        }
+
+        Ok(variables)
+    }
+}
+
+/**
+ * Extract value at JSON Pointer (such as `/database/password`) from JSON content if `extract` is set,
+ * returning content as-is otherwise. String values are returned as-is, other values as JSON.
+ *
+ * As dummy secrets aren't JSON, dry-run returns `<content>:<pointer>` for non-JSON content.
+ */
+pub fn extract_json_value(ctx: &NovopsContext, content: String, extract: &Option<String>) -> Result<String, anyhow::Error> {
+    let pointer = match extract {
+        Some(p) => p,
+        None => return Ok(content)
+    };
+
+    if ctx.dry_run && serde_json::from_str::<Value>(&content).is_err() {
+        return Ok(format!("{}:{}", content, pointer));
+    }
+
+    let json: Value = serde_json::from_str(&content)
+        .with_context(|| format!("Couldn't parse secret as JSON to extract '{}'", pointer))?;
+
+    Ok(json_value_string(json_pointer(&json, pointer)?))
+}
+
+/// Same as `extract_json_value` for bytes content, which must be valid UTF-8 if `extract` is set
+pub fn extract_json_bytes(ctx: &NovopsContext, content: Vec<u8>, extract: &Option<String>) -> Result<Vec<u8>, anyhow::Error> {
+    if extract.is_none() {
+        return Ok(content);
+    }
+
+    let content = String::from_utf8(content)
+        .with_context(|| "Couldn't convert secret bytes to UTF-8 String to extract JSON value")?;
+
+    Ok(extract_json_value(ctx, content, extract)?.into_bytes())
+}
+
+fn json_pointer<'a>(json: &'a Value, pointer: &str) -> Result<&'a Value, anyhow::Error> {
+    if !pointer.is_empty() && !pointer.starts_with('/') {
+        return Err(anyhow::anyhow!("Invalid JSON Pointer '{}': must start with '/', such as '/password'", pointer));
+    }
+
+    json.pointer(pointer)
+        .ok_or(anyhow::anyhow!("JSON Pointer '{}' not found in secret", pointer))
+}
+
+/// String values as-is, other values as JSON
+fn json_value_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string()
+    }
+}
+
+/// Flatten JSON object into key/value pairs, keys of nested objects being joined by separator
+fn flatten_json_object(json: &Value, prefix: &str, separator: &str, entries: &mut Vec<(String, String)>) -> Result<(), anyhow::Error> {
+    let object = json.as_object()
+        .ok_or(anyhow::anyhow!("Expected a JSON object to expand, got: {}", json_type(json)))?;
+
+    for (key, value) in object {
+        let key = if prefix.is_empty() { key.clone() } else { format!("{}{}{}", prefix, separator, key) };
+
+        match value {
+            Value::Object(_) => flatten_json_object(value, &key, separator, entries)?,
+            other => entries.push((key, json_value_string(other)))
+        }
+    }
+
+    Ok(())
+}
+
+fn json_type(json: &Value) -> &'static str {
+    match json {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_json_value() {
+        let ctx = NovopsContext::default();
+        let secret = String::from(r#"{"username": "admin", "port": 5432, "db": {"host": "localhost", "tags": ["a", "b"]}}"#);
+        let extract = |p: &str| extract_json_value(&ctx, secret.clone(), &Some(p.to_string()));
+
+        assert_eq!(extract("/username").unwrap(), "admin");
+        assert_eq!(extract("/port").unwrap(), "5432");
+        assert_eq!(extract("/db/host").unwrap(), "localhost");
+        assert_eq!(extract("/db/tags").unwrap(), r#"["a","b"]"#);
+        assert_eq!(extract_json_value(&ctx, secret.clone(), &None).unwrap(), secret);
+
+        assert!(extract("/unknown").is_err());
+        assert!(extract("username").is_err());
+        assert!(extract_json_value(&ctx, String::from("not json"), &Some(String::from("/username"))).is_err());
+    }
+
+    #[test]
+    fn test_flatten_json_object() {
+        let json: Value = serde_json::from_str(r#"{"user": "admin", "db": {"host": "localhost", "port": 5432}}"#).unwrap();
+
+        let mut entries = vec![];
+        flatten_json_object(&json, "", ".", &mut entries).unwrap();
+        assert_eq!(entries, vec![
+            (String::from("db.host"), String::from("localhost")),
+            (String::from("db.port"), String::from("5432")),
+            (String::from("user"), String::from("admin")),
+        ]);
+
+        assert!(flatten_json_object(&Value::from("string"), "", "_", &mut vec![]).is_err());
+    }
+}
@@ -5,4 +5,8 @@ pub mod variables;
 pub mod hashivault;
 pub mod gcloud;
 pub mod azure;
-pub mod sops;
\ No newline at end of file
+pub mod sops;
+pub mod template;
+pub mod first_of;
+pub mod json;
+pub mod transform;
\ No newline at end of file
@@ -0,0 +1,152 @@
+use std::io::Read;
+use serde::Deserialize;
+use async_trait::async_trait;
+use anyhow::{self, Context};
+use schemars::JsonSchema;
+use log::warn;
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use flate2::read::GzDecoder;
+use percent_encoding::{percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+use crate::core::{ResolveTo, NovopsContext, BytesResolvableInput};
+
+/// Transform a resolved value, such as decoding a base64 encoded certificate
+#[derive(Debug, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct TransformInput {
+    /// Value to transform. Can be a plain string or any Input.
+    pub value: Box<BytesResolvableInput>,
+
+    /// Transformations applied in order on resolved value, such as `[ trim, base64_decode, gzip_decompress ]`
+    pub transform: Vec<Transform>
+}
+
+/// Transformation applied on a resolved value
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Transform {
+    /// Decode standard base64, ignoring whitespaces and line breaks
+    Base64Decode,
+
+    /// Encode as standard base64
+    Base64Encode,
+
+    /// Remove leading and trailing whitespaces and line breaks
+    Trim,
+
+    /// Decode hexadecimal string
+    HexDecode,
+
+    /// Encode as lowercase hexadecimal string
+    HexEncode,
+
+    /// Decompress gzip data
+    GzipDecompress,
+
+    /// Convert JSON document to YAML
+    JsonToYaml,
+
+    /// Convert YAML document to JSON
+    YamlToJson,
+
+    /// Percent-encode value to use it in a URL, such as a password in a database URL
+    UrlEncode
+}
+
+impl TransformInput {
+    /// Names of other variables this Input references, which must be resolved first
+    pub fn referenced_variables(&self) -> Result<Vec<String>, anyhow::Error> {
+        self.value.referenced_variables()
+    }
+}
+
+#[async_trait]
+impl ResolveTo<Vec<u8>> for TransformInput {
+    async fn resolve(&self, ctx: &NovopsContext) -> Result<Vec<u8>, anyhow::Error> {
+        let mut value = self.value.resolve(ctx).await?;
+
+        for t in &self.transform {
+            value = match apply_transform(*t, value.clone()) {
+                Ok(v) => v,
+
+                // dummy secrets are usually not valid for transformation, keep them as-is
+                Err(e) if ctx.dry_run => {
+                    warn!("Dry-run: ignoring failed transformation {:?}: {:#}", t, e);
+                    value
+                },
+                Err(e) => return Err(e.context(format!("Couldn't apply transformation {:?}", t)))
+            };
+        }
+
+        Ok(value)
+    }
+}
+
+#[async_trait]
+impl ResolveTo<String> for TransformInput {
+    async fn resolve(&self, ctx: &NovopsContext) -> Result<String, anyhow::Error> {
+        let value: Vec<u8> = self.resolve(ctx).await?;
+
+        String::from_utf8(value)
+            .with_context(|| format!("Transformed value is not a valid UTF-8 string (transformations: {:?}). \
+                Binary data can only be used as file content.", &self.transform))
+    }
+}
+
+/// Characters encoded by `url_encode`: all except unreserved characters (RFC 3986)
+const URL_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_').remove(b'~');
+
+fn apply_transform(transform: Transform, value: Vec<u8>) -> Result<Vec<u8>, anyhow::Error> {
+    match transform {
+        Transform::Base64Decode => {
+            let cleaned: Vec<u8> = value.into_iter().filter(|b| !b.is_ascii_whitespace()).collect();
+            Ok(BASE64.decode(cleaned)?)
+        },
+        Transform::Base64Encode => Ok(BASE64.encode(value).into_bytes()),
+        Transform::Trim => Ok(value.trim_ascii().to_vec()),
+        Transform::HexDecode => Ok(hex::decode(value.trim_ascii())?),
+        Transform::HexEncode => Ok(hex::encode(value).into_bytes()),
+        Transform::GzipDecompress => {
+            let mut result = vec![];
+            GzDecoder::new(value.as_slice()).read_to_end(&mut result)?;
+            Ok(result)
+        },
+        Transform::JsonToYaml => {
+            let json: serde_json::Value = serde_json::from_slice(&value)?;
+            Ok(serde_yaml::to_string(&json)?.into_bytes())
+        },
+        Transform::YamlToJson => {
+            let yaml: serde_json::Value = serde_yaml::from_slice(&value)?;
+            Ok(serde_json::to_string(&yaml)?.into_bytes())
+        },
+        Transform::UrlEncode => Ok(percent_encode(&value, URL_ENCODE_SET).to_string().into_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use flate2::{write::GzEncoder, Compression};
+
+    #[test]
+    fn test_apply_transform() {
+        let apply = |t: Transform, v: &str| apply_transform(t, v.as_bytes().to_vec()).unwrap();
+
+        assert_eq!(apply(Transform::Base64Decode, "aGVs\nbG8=\n"), b"hello");
+        assert_eq!(apply(Transform::Base64Encode, "hello"), b"aGVsbG8=");
+        assert_eq!(apply(Transform::Trim, " \n hello \n"), b"hello");
+        assert_eq!(apply(Transform::HexDecode, "68656c6c6f\n"), b"hello");
+        assert_eq!(apply(Transform::HexEncode, "hello"), b"68656c6c6f");
+        assert_eq!(apply(Transform::JsonToYaml, r#"{"foo": "bar"}"#), b"---\nfoo: bar\n");
+        assert_eq!(apply(Transform::YamlToJson, "foo: bar\n"), br#"{"foo":"bar"}"#);
+        assert_eq!(apply(Transform::UrlEncode, "host.io:5432/?a=b&c d+e_~"), b"host.io%3A5432%2F%3Fa%3Db%26c%20d%2Be_~");
+
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(b"hello").unwrap();
+        assert_eq!(apply_transform(Transform::GzipDecompress, encoder.finish().unwrap()).unwrap(), b"hello");
+
+        assert!(apply_transform(Transform::Base64Decode, b"not base64!".to_vec()).is_err());
+        assert!(apply_transform(Transform::GzipDecompress, b"not gzip".to_vec()).is_err());
+        assert!(apply_transform(Transform::JsonToYaml, b"{ not json".to_vec()).is_err());
+    }
+}
@@ -1,6 +1,7 @@
 use serde::Deserialize;
 use async_trait::async_trait;
 use crate::core::{ResolveTo, StringResolvableInput, NovopsContext};
+use crate::retry::RetryConfig;
 use anyhow;
 use schemars::JsonSchema;
 
@@ -10,7 +11,19 @@ pub struct VariableInput {
     pub name: String,
 
     /// Source of truth for variable 
-    pub value: StringResolvableInput
+    pub value: StringResolvableInput,
+
+    /// Retry policy for this variable, overriding `config.default.retry`
+    pub retry: Option<RetryConfig>,
+
+    /// Timeout in seconds for each attempt to resolve this variable, overriding `config.default.timeout`
+    pub timeout: Option<u64>,
+
+    /// If true, variable is not set when its value can't be resolved, failure being logged as a warning. Default: false.
+    pub optional: Option<bool>,
+
+    /// Value to use when variable's value can't be resolved, failure being logged as a warning
+    pub default: Option<String>
 }
     
 /**
@@ -37,4 +50,14 @@ impl ResolveTo<VariableOutput> for VariableInput {
             }
         )
     }
-}
\ No newline at end of file
+}
+
+/// Convert name to upper snake case variable name, such as `db/api-key` to `DB_API_KEY`: 
+/// characters other than letters and digits are replaced by `_`, leading and trailing `_` are removed
+pub fn upper_snake_case(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect::<String>()
+        .trim_matches('_')
+        .to_string()
+}
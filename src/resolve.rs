@@ -1,14 +1,112 @@
-use log::info;
+use std::collections::{HashMap, HashSet};
+use log::{info, warn};
 use tokio::task::JoinSet;
 use anyhow::Context;
 
 use crate::{
-    core::{NovopsContext, NovopsEnvironmentInput, ResolveTo}, 
-    modules::{aws::config::AwsInput, files::{FileInput, FileOutput}, hashivault::config::HashiVaultInput, sops::SopsDotenvInput, variables::{VariableInput, VariableOutput}}
+    retry::RetryPolicy,
+    core::{NovopsContext, NovopsEnvironmentInput, ResolveTo, BytesResolvableInput, StringResolvableInput}, 
+    modules::{aws::{config::AwsInput, ssm::AwsSSMParametersByPathInput, caller_identity::AwsStsCallerIdentityInput, prefetch::prefetch_aws_inputs, client::session_credentials_from_outputs, profile::{AwsProfileCredentials, AwsCredentialsProfileInput, build_profile_outputs, backend_region}}, files::{FileInput, FileOutput}, hashivault::{config::HashiVaultInput, kv2::HashiVaultKeyValueV2AllInput}, json::ExpandJsonInput, sops::SopsDotenvInput, variables::{VariableInput, VariableOutput}}
 };
 
+type ResolveOutput = Result<(Vec<VariableOutput>, Vec<FileOutput>, Vec<InputFallback>), anyhow::Error>;
+
+/// Variable or file input which failed to resolve and was skipped or set to its `default`
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputFallback {
+    /// Variable name, or file variable, symlink, dest or name
+    pub input: String,
+
+    pub outcome: FallbackOutcome,
+
+    /// Error which caused fallback
+    pub reason: String
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FallbackOutcome {
+    /// Optional input was skipped, no output generated
+    Skipped,
+
+    /// Input `default` was used as value
+    DefaultUsed
+}
+
+/// Variable or file input waiting for the variables it references to be resolved
+enum PendingInput {
+    Variable(VariableInput, HashSet<String>),
+    File(FileInput, HashSet<String>)
+}
+
+impl PendingInput {
+    fn dependencies(&self) -> &HashSet<String> {
+        match self {
+            PendingInput::Variable(_, deps) => deps,
+            PendingInput::File(_, deps) => deps,
+        }
+    }
+
+    fn description(&self) -> String {
+        match self {
+            PendingInput::Variable(v, _) => format!("variable input {:}", &v.name),
+            PendingInput::File(f, _) => format!("file input {:?}", file_input_name(f)),
+        }
+    }
+}
+
+/// Inputs declared with a fallback behavior, whether they fail to resolve or not
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DeclaredFallbacks {
+    /// Optional inputs, not generated if they fail to resolve
+    pub optional: Vec<String>,
+
+    /// Inputs with a `default` used if they fail to resolve
+    pub defaults: Vec<String>,
+
+    /// Inputs trying several sources in order with `first_of`
+    pub first_of: Vec<String>
+}
+
+/// Variable and file inputs of environment declared as `optional`, with a `default` or using `first_of`
+pub fn declared_fallbacks(inputs: &NovopsEnvironmentInput) -> DeclaredFallbacks {
+    let mut result = DeclaredFallbacks::default();
+
+    let variables = inputs.variables.iter().flatten()
+        .map(|v| (v.name.clone(), v.optional, v.default.is_some(), matches!(v.value, StringResolvableInput::FirstOfInput(_))));
+    let files = inputs.files.iter().flatten()
+        .map(|f| (file_input_name(f), f.optional, f.default.is_some(), 
+            matches!(f.content, BytesResolvableInput::StringResolvableInput(StringResolvableInput::FirstOfInput(_)))));
+
+    for (name, optional, default, first_of) in variables.chain(files) {
+        if optional.unwrap_or(false) {
+            result.optional.push(name.clone());
+        }
+        if default {
+            result.defaults.push(name.clone());
+        }
+        if first_of {
+            result.first_of.push(name);
+        }
+    }
+
+    result
+}
+
+/// Name identifying file input in logs and outputs
+fn file_input_name(f: &FileInput) -> String {
+    f.variable.clone()
+        .or(f.symlink.clone())
+        .or(f.dest.clone())
+        .or(f.name.clone())
+        .unwrap_or_default()
+}
+
+/**
+ * Resolve all inputs of an environment. Optional inputs or inputs with a `default` failing to resolve 
+ * are logged as warnings and returned as InputFallback instead of failing.
+ */
 pub async fn resolve_environment_inputs_parallel(ctx: &NovopsContext, inputs: NovopsEnvironmentInput) 
-    -> Result<(Vec<VariableOutput>, Vec<FileOutput>), anyhow::Error>
+    -> Result<(Vec<VariableOutput>, Vec<FileOutput>, Vec<InputFallback>), anyhow::Error>
 {
     
     //
@@ -17,52 +115,154 @@ pub async fn resolve_environment_inputs_parallel(ctx: &NovopsContext, inputs: No
     // Run a future resolving to a generic (Vec<VariableOutput>, Vec<FileOutput>) type
     // So that each can be run in parallel in a single JoinSet. 
     //
+    // Variables and files may reference other variables (e.g. with templates):
+    // they're spawned as soon as all variables they reference are resolved
+    // so that independent inputs are still resolved in parallel.
+    //
+
+    let variables = inputs.variables.unwrap_or_default();
+    let files = inputs.files.unwrap_or_default();
+
+    let variable_names: HashSet<String> = variables.iter()
+        .map(|v| v.name.clone())
+        .collect();
+
+    let mut pending = build_pending_inputs(variables.clone(), files.clone())?;
+
+    // Inputs generating AWS credentials with `use_for_inputs` are resolved first, 
+    // in order Hashivault then AWS assume role, so that following AWS Inputs use their credentials.
+    // Setting both chains them: Hashivault credentials are used to assume role.
+    // Inputs with `credentials_profile` and `aws_credentials_profiles` are also resolved first to write all profiles in a single credentials file.
+    let mut ctx = ctx.clone();
+    let mut aws_input = inputs.aws;
+    let mut hashivault_input = inputs.hashivault;
+    let mut aws_profiles = vec![];
+    let mut output_results = vec![];
+
+    if let Some(hv) = hashivault_input.take_if(|h| h.aws.use_for_inputs.unwrap_or(false) || h.aws.credentials_profile.is_some()) {
+        let (use_for_inputs, profile) = (hv.aws.use_for_inputs.unwrap_or(false), hv.aws.credentials_profile.clone());
+
+        let output = resolve_and_wrap_hashivault_input(ctx.clone(), Some(hv)).await
+            .with_context(|| "Couldn't generate Hashivault AWS credentials")?;
+
+        if use_for_inputs {
+            ctx.aws_session_credentials = Some(session_credentials_from_outputs(&output.0)?);
+        }
+        match profile {
+            Some(profile) => aws_profiles.push(AwsProfileCredentials { profile, region: backend_region(&ctx, &None)?, credentials: output.0 }),
+            None => output_results.push(Ok(output))
+        }
+    }
+
+    if let Some(aws) = aws_input.take_if(|a| a.use_for_inputs() || a.credentials_profile().is_some()) {
+        let (use_for_inputs, profile, backend) = (aws.use_for_inputs(), aws.credentials_profile(), aws.backend());
+
+        let output = resolve_and_wrap_aws_input(ctx.clone(), Some(aws)).await
+            .with_context(|| "Couldn't generate AWS credentials")?;
+
+        if use_for_inputs {
+            ctx.aws_session_credentials = Some(session_credentials_from_outputs(&output.0)?);
+        }
+        match profile {
+            Some(profile) => aws_profiles.push(AwsProfileCredentials { profile, region: backend_region(&ctx, &backend)?, credentials: output.0 }),
+            None => output_results.push(Ok(output))
+        }
+    }
+
+    let profiles_input = inputs.aws_credentials_profiles.unwrap_or_default();
+    for profile in profiles_input.profiles.unwrap_or_default() {
+        let name = profile.name.clone();
+        aws_profiles.push(resolve_aws_credentials_profile(&ctx, profile).await
+            .with_context(|| format!("Couldn't generate AWS credentials for profile '{}'", name))?);
+    }
+
+    if !aws_profiles.is_empty() || profiles_input.default_profile.is_some() {
+        let (vars, files) = build_profile_outputs(&ctx, &aws_profiles, &profiles_input.default_profile).await?;
+        output_results.push(Ok((vars, files, vec![])));
+    }
+
+    let ctx = &ctx;
+
+    // Read AWS secrets with batch APIs first, identical reads are done once per load
+    prefetch_aws_inputs(ctx, &variables, &files).await;
+    let mut resolved_variables: HashMap<String, String> = HashMap::new();
 
     // Spawn every resolve tasks into JoinSet
     let mut resolve_tasks = JoinSet::new();
 
-    for i in inputs.variables.unwrap_or_default() {
-        let var_fut = resolve_and_wrap_variable_input(ctx.clone(), i);
-        resolve_tasks.spawn(var_fut);
-    };
-
-    for f in inputs.files.unwrap_or_default() {
-        let file_fut = resolve_and_wrap_file_input(ctx.clone(), f);
-        resolve_tasks.spawn(file_fut);
-    };
+    spawn_ready_inputs(&mut resolve_tasks, &mut pending, ctx, &resolved_variables);
 
     let sops = resolve_and_wrap_sops_input(ctx.clone(), inputs.sops_dotenv);
     resolve_tasks.spawn(sops);
 
-    let aws = resolve_and_wrap_aws_input(ctx.clone(), inputs.aws);
+    let ssm_by_path = resolve_and_wrap_ssm_by_path_input(ctx.clone(), inputs.aws_ssm_parameters_by_path);
+    resolve_tasks.spawn(ssm_by_path);
+
+    let expand_json = resolve_and_wrap_expand_json_input(ctx.clone(), inputs.expand_json);
+    resolve_tasks.spawn(expand_json);
+
+    let kv2_all = resolve_and_wrap_kv2_all_input(ctx.clone(), inputs.hvault_kv2_all);
+    resolve_tasks.spawn(kv2_all);
+
+    let caller_identity = resolve_and_wrap_caller_identity_input(ctx.clone(), inputs.aws_sts_caller_identity);
+    resolve_tasks.spawn(caller_identity);
+
+    let aws = resolve_and_wrap_aws_input(ctx.clone(), aws_input);
     resolve_tasks.spawn(aws);
     
-    let hashivault = resolve_and_wrap_hashivault_input(ctx.clone(), inputs.hashivault);
+    let hashivault = resolve_and_wrap_hashivault_input(ctx.clone(), hashivault_input);
     resolve_tasks.spawn(hashivault);
 
-    // Await on each output result
-    let mut output_results = vec![];
-    while let Some(res) = resolve_tasks.join_next().await {
-    
-        // Result is imbricated Result<Result<_, anyhow::Error>, JoinError>
-        // Wrap potential JoinError as anyhow::Error
-        let wrapped_res = match res {
-            Ok(ok) => ok,
-            Err(err) => Err(anyhow::anyhow!(err)),
-        };
+    loop {
+        // Await on each output result
+        while let Some(res) = resolve_tasks.join_next().await {
+        
+            // Result is imbricated Result<Result<_, anyhow::Error>, JoinError>
+            // Wrap potential JoinError as anyhow::Error
+            let wrapped_res = match res {
+                Ok(ok) => ok,
+                Err(err) => Err(anyhow::anyhow!(err)),
+            };
+
+            // Spawn inputs referencing newly resolved variables
+            if let Ok(o) = &wrapped_res {
+                for v in o.0.iter().filter(|v| variable_names.contains(&v.name)) {
+                    resolved_variables.insert(v.name.clone(), v.value.clone());
+                }
+                spawn_ready_inputs(&mut resolve_tasks, &mut pending, ctx, &resolved_variables);
+            }
+
+            output_results.push(wrapped_res);
+        }
+
+        // Inputs referencing variables which failed to resolve or were skipped fall back to their own
+        // `default` or `optional`. A default value may in turn let other pending inputs be resolved.
+        if !spawn_blocked_inputs_fallback(&mut resolve_tasks, &mut pending, ctx, &resolved_variables) {
+            break;
+        }
+    }
+
+    // Inputs still pending can't be resolved. Not expected as variables referencing each other are rejected beforehand.
+    for p in pending {
+        let mut failed_deps: Vec<&String> = p.dependencies().iter()
+            .filter(|d| !resolved_variables.contains_key(*d))
+            .collect();
+        failed_deps.sort();
 
-        output_results.push(wrapped_res);
+        output_results.push(Err(anyhow::anyhow!("Couldn't resolve {} as referenced variable(s) {:?} failed to resolve", p.description(), failed_deps)));
     }
 
     // Parse all outputs and discriminate ok and errors
     let mut var_outputs = vec![];
     let mut file_outputs = vec![];
+    let mut fallbacks = vec![];
     let mut resolve_errors = vec![];
     for result in output_results {
         match result {
             Ok(o) => {
                 var_outputs.extend(o.0);
                 file_outputs.extend(o.1);
+                fallbacks.extend(o.2);
             },
             Err(err) => resolve_errors.push(err),
         }
@@ -77,37 +277,262 @@ pub async fn resolve_environment_inputs_parallel(ctx: &NovopsContext, inputs: No
         return Err(anyhow::format_err!(final_message));
     }
 
-    Ok( (var_outputs, file_outputs) )
+    Ok( (var_outputs, file_outputs, fallbacks) )
     
 }
 
-async fn resolve_and_wrap_file_input(ctx: NovopsContext, f: FileInput) -> Result<(Vec<VariableOutput>, Vec<FileOutput>), anyhow::Error>  {
+/**
+ * Resolve a single Input generating AWS credentials, `aws` or `hashivault`, and return credentials variables.
+ * Default to `aws` if set, `hashivault` otherwise. 
+ * 
+ * Hashivault credentials with `use_for_inputs` are used to resolve `aws`, as when loading environment.
+ */
+pub async fn resolve_aws_credentials_input(ctx: &NovopsContext, inputs: NovopsEnvironmentInput, input: &Option<String>) 
+    -> Result<Vec<VariableOutput>, anyhow::Error>
+{
+    let input = match input {
+        Some(i) => i.clone(),
+        None if inputs.aws.is_some() => String::from("aws"),
+        None => String::from("hashivault")
+    };
+
+    match input.as_str() {
+        "aws" => {
+            let aws = inputs.aws
+                .ok_or(anyhow::anyhow!("No `aws` Input in environment '{}'", &ctx.env_name))?;
+
+            let mut ctx = ctx.clone();
+            if let Some(hv) = inputs.hashivault.filter(|h| h.aws.use_for_inputs.unwrap_or(false)) {
+                let output = resolve_and_wrap_hashivault_input(ctx.clone(), Some(hv)).await
+                    .with_context(|| "Couldn't generate Hashivault AWS credentials")?;
+                ctx.aws_session_credentials = Some(session_credentials_from_outputs(&output.0)?);
+            }
+
+            Ok(resolve_and_wrap_aws_input(ctx, Some(aws)).await?.0)
+        },
+        "hashivault" => {
+            let hv = inputs.hashivault
+                .ok_or(anyhow::anyhow!("No `aws` or `hashivault` Input in environment '{}'", &ctx.env_name))?;
+
+            Ok(resolve_and_wrap_hashivault_input(ctx.clone(), Some(hv)).await?.0)
+        },
+        _ => Err(anyhow::anyhow!("Unknown AWS credentials Input '{}'. Expected 'aws' or 'hashivault'", input))
+    }
+}
+
+/// Check variables referenced by variable and file inputs exist and don't form a cycle
+fn build_pending_inputs(variables: Vec<VariableInput>, files: Vec<FileInput>) -> Result<Vec<PendingInput>, anyhow::Error> {
+
+    let mut pending = vec![];
+    for v in variables {
+        let deps = v.value.referenced_variables()
+            .with_context(|| format!("Invalid variable input {:}", &v.name))?;
+        pending.push(PendingInput::Variable(v, deps.into_iter().collect()));
+    }
+    for f in files {
+        let deps = f.content.referenced_variables()
+            .with_context(|| format!("Invalid file input {:?}", &f))?;
+        pending.push(PendingInput::File(f, deps.into_iter().collect()));
+    }
+
+    let variable_deps: HashMap<String, HashSet<String>> = pending.iter()
+        .filter_map(|p| match p {
+            PendingInput::Variable(v, deps) => Some((v.name.clone(), deps.clone())),
+            PendingInput::File(_, _) => None,
+        })
+        .collect();
+
+    for p in pending.iter() {
+        let mut unknown: Vec<&String> = p.dependencies().iter()
+            .filter(|d| !variable_deps.contains_key(*d))
+            .collect();
+        unknown.sort();
+
+        if !unknown.is_empty() {
+            return Err(anyhow::anyhow!("{} references unknown variable(s) {:?}. \
+                Only variables defined under `variables` of the same environment can be referenced.", p.description(), unknown));
+        }
+    }
+
+    // Remove variables without unresolved dependencies until none can be removed
+    // Remaining variables are part of a cycle
+    let mut remaining = variable_deps;
+    loop {
+        let resolvable: Vec<String> = remaining.iter()
+            .filter(|(_, deps)| deps.iter().all(|d| !remaining.contains_key(d)))
+            .map(|(name, _)| name.clone())
+            .collect();
+        
+        if resolvable.is_empty() {
+            break;
+        }
+
+        for name in resolvable {
+            remaining.remove(&name);
+        }
+    }
+
+    if !remaining.is_empty() {
+        let mut cycle: Vec<String> = remaining.into_keys().collect();
+        cycle.sort();
+        return Err(anyhow::anyhow!("Variables {:?} reference each other in a cycle and can't be resolved", cycle));
+    }
+
+    Ok(pending)
+}
+
+/// Spawn pending inputs for which all referenced variables are resolved
+fn spawn_ready_inputs(resolve_tasks: &mut JoinSet<ResolveOutput>, pending: &mut Vec<PendingInput>, 
+    ctx: &NovopsContext, resolved_variables: &HashMap<String, String>) {
+
+    let (ready, not_ready): (Vec<PendingInput>, Vec<PendingInput>) = pending.drain(..)
+        .partition(|p| p.dependencies().iter().all(|d| resolved_variables.contains_key(d)));
+
+    *pending = not_ready;
+
+    for p in ready {
+        // Only expose referenced variables to input
+        let mut input_ctx = ctx.clone();
+        input_ctx.resolved_variables = resolved_variables.iter()
+            .filter(|(name, _)| p.dependencies().contains(*name))
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+
+        match p {
+            PendingInput::Variable(v, _) => resolve_tasks.spawn(resolve_and_wrap_variable_input(input_ctx, v)),
+            PendingInput::File(f, _) => resolve_tasks.spawn(resolve_and_wrap_file_input(input_ctx, f)),
+        };
+    }
+}
+
+/**
+ * Spawn fallback of pending inputs referencing variables which won't be resolved: 
+ * variables which failed to resolve or were skipped, and are not pending themselves.
+ * Returns false if there is no such input.
+ */
+fn spawn_blocked_inputs_fallback(resolve_tasks: &mut JoinSet<ResolveOutput>, pending: &mut Vec<PendingInput>, 
+    ctx: &NovopsContext, resolved_variables: &HashMap<String, String>) -> bool {
+
+    let pending_names: HashSet<String> = pending.iter()
+        .filter_map(|p| match p {
+            PendingInput::Variable(v, _) => Some(v.name.clone()),
+            PendingInput::File(_, _) => None,
+        })
+        .collect();
+
+    let failed_deps = |p: &PendingInput| -> Vec<String> {
+        let mut deps: Vec<String> = p.dependencies().iter()
+            .filter(|d| !resolved_variables.contains_key(*d) && !pending_names.contains(*d))
+            .cloned()
+            .collect();
+        deps.sort();
+        deps
+    };
+
+    let (blocked, not_blocked): (Vec<PendingInput>, Vec<PendingInput>) = pending.drain(..)
+        .partition(|p| !failed_deps(p).is_empty());
+
+    *pending = not_blocked;
+
+    let spawned = !blocked.is_empty();
+    for p in blocked {
+        let err = anyhow::anyhow!("Couldn't resolve {} as referenced variable(s) {:?} failed to resolve", p.description(), failed_deps(&p));
+
+        match p {
+            PendingInput::Variable(v, _) => resolve_tasks.spawn(async move { variable_fallback_output(&v, err) }),
+            PendingInput::File(f, _) => {
+                let ctx = ctx.clone();
+                resolve_tasks.spawn(async move { file_fallback_output(&ctx, &f, err).await })
+            },
+        };
+    }
+
+    spawned
+}
+
+async fn resolve_and_wrap_file_input(ctx: NovopsContext, f: FileInput) -> ResolveOutput {
 
     info!("Resolving file input {:?}", &f);
 
-    let result = f.resolve(&ctx).await
-        .with_context(|| format!("Couldn't resolve file input {:?}", &f))
-        .map(|o| (vec![], vec![o]) )?;
+    let name = file_input_name(&f);
+    let policy = RetryPolicy::for_input(&ctx, &f.retry, &f.timeout);
+    let result = policy.run(&format!("file input {:?}", &name), || f.resolve(&ctx)).await
+        .with_context(|| format!("Couldn't resolve file input {:?}", &f));
+
+    let output = match result {
+        Ok(o) => (vec![], vec![o], vec![]),
+        Err(e) => file_fallback_output(&ctx, &f, e).await?
+    };
 
     info!("Resolved file input {:?}", &f);
 
-    Ok(result)
+    Ok(output)
 }
 
-async fn resolve_and_wrap_variable_input(ctx: NovopsContext, i: VariableInput) -> Result<(Vec<VariableOutput>, Vec<FileOutput>), anyhow::Error>  {
+/// Output for file input which failed to resolve: its `default` content, skipped if `optional` or error otherwise
+async fn file_fallback_output(ctx: &NovopsContext, f: &FileInput, e: anyhow::Error) -> ResolveOutput {
+    let name = file_input_name(f);
+
+    match &f.default {
+        Some(default) => {
+            warn!("Couldn't resolve file input {:?}, using default content: {:?}", &name, e);
+
+            let default_input = FileInput { 
+                content: BytesResolvableInput::ByteVec(default.clone().into_bytes()),
+                ..f.clone()
+            };
+            let o = default_input.resolve(ctx).await
+                .with_context(|| format!("Couldn't generate file input {:?} with default content", &name))?;
+
+            Ok((vec![], vec![o], vec![fallback(name, FallbackOutcome::DefaultUsed, e)]))
+        },
+        None if f.optional.unwrap_or(false) => {
+            warn!("Couldn't resolve optional file input {:?}, skipping it: {:?}", &name, e);
+            Ok((vec![], vec![], vec![fallback(name, FallbackOutcome::Skipped, e)]))
+        },
+        None => Err(e)
+    }
+}
+
+async fn resolve_and_wrap_variable_input(ctx: NovopsContext, i: VariableInput) -> ResolveOutput {
 
     info!("Resolving variable input {:}", &i.name);
 
-    let result = i.resolve(&ctx).await
-        .with_context(|| format!("Couldn't resolve variable input {:}", &i.name))
-        .map(|o| (vec![o], vec![]) )?;
+    let policy = RetryPolicy::for_input(&ctx, &i.retry, &i.timeout);
+    let result = policy.run(&format!("variable input {:}", &i.name), || i.resolve(&ctx)).await
+        .with_context(|| format!("Couldn't resolve variable input {:}", &i.name));
+
+    let output = match result {
+        Ok(o) => (vec![o], vec![], vec![]),
+        Err(e) => variable_fallback_output(&i, e)?
+    };
 
     info!("Resolved variable input {:}", &i.name);
 
-    Ok(result)
+    Ok(output)
+}
+
+/// Output for variable input which failed to resolve: its `default` value, skipped if `optional` or error otherwise
+fn variable_fallback_output(i: &VariableInput, e: anyhow::Error) -> ResolveOutput {
+    match &i.default {
+        Some(default) => {
+            warn!("Couldn't resolve variable input {:}, using default value: {:?}", &i.name, e);
+            let o = VariableOutput { name: i.name.clone(), value: default.clone() };
+            Ok((vec![o], vec![], vec![fallback(i.name.clone(), FallbackOutcome::DefaultUsed, e)]))
+        },
+        None if i.optional.unwrap_or(false) => {
+            warn!("Couldn't resolve optional variable input {:}, skipping it: {:?}", &i.name, e);
+            Ok((vec![], vec![], vec![fallback(i.name.clone(), FallbackOutcome::Skipped, e)]))
+        },
+        None => Err(e)
+    }
 }
 
-async fn resolve_and_wrap_sops_input(ctx: NovopsContext, sops_vec_opt: Option<Vec<SopsDotenvInput>>) -> Result<(Vec<VariableOutput>, Vec<FileOutput>), anyhow::Error>  {
+fn fallback(input: String, outcome: FallbackOutcome, err: anyhow::Error) -> InputFallback {
+    InputFallback { input, outcome, reason: format!("{:#}", err) }
+}
+
+async fn resolve_and_wrap_sops_input(ctx: NovopsContext, sops_vec_opt: Option<Vec<SopsDotenvInput>>) -> ResolveOutput {
 
     match sops_vec_opt {
         Some(sops_vec) => {
@@ -115,8 +540,9 @@ async fn resolve_and_wrap_sops_input(ctx: NovopsContext, sops_vec_opt: Option<Ve
 
             let mut result = Vec::new();
 
+            let policy = RetryPolicy::for_input(&ctx, &None, &None);
             for sops in sops_vec {
-                let r = sops.resolve(&ctx).await
+                let r = policy.run("SOPS Dotenv input", || sops.resolve(&ctx)).await
                     .with_context(|| format!("Could not resolve SopsDotenv input {:?}", sops))?;
 
                 result.extend(r);
@@ -124,47 +550,163 @@ async fn resolve_and_wrap_sops_input(ctx: NovopsContext, sops_vec_opt: Option<Ve
 
             info!("Resolved SOPS Dotenv inputs");
 
-            Ok( (result, vec![]) )
+            Ok( (result, vec![], vec![]) )
         },
-        None => Ok( (vec![], vec![]) )
+        None => Ok( (vec![], vec![], vec![]) )
     }
 }
 
-async fn resolve_and_wrap_hashivault_input(ctx: NovopsContext, hashivault: Option<HashiVaultInput>) -> Result<(Vec<VariableOutput>, Vec<FileOutput>), anyhow::Error> {
+async fn resolve_and_wrap_ssm_by_path_input(ctx: NovopsContext, inputs: Option<Vec<AwsSSMParametersByPathInput>>) -> ResolveOutput {
+
+    match inputs {
+        Some(inputs) => {
+            info!("Resolving AWS SSM parameters by path inputs");
+
+            let mut result = Vec::new();
+
+            let policy = RetryPolicy::for_input(&ctx, &None, &None);
+            for input in inputs {
+                let r = policy.run("AWS SSM parameters by path input", || input.resolve(&ctx)).await
+                    .with_context(|| format!("Could not resolve AWS SSM parameters by path input {:?}", input.path))?;
+
+                result.extend(r);
+            }
+
+            info!("Resolved AWS SSM parameters by path inputs");
+
+            Ok( (result, vec![], vec![]) )
+        },
+        None => Ok( (vec![], vec![], vec![]) )
+    }
+}
+
+async fn resolve_and_wrap_expand_json_input(ctx: NovopsContext, inputs: Option<Vec<ExpandJsonInput>>) -> ResolveOutput {
+
+    match inputs {
+        Some(inputs) => {
+            info!("Resolving expand JSON inputs");
+
+            let mut result = Vec::new();
+
+            let policy = RetryPolicy::for_input(&ctx, &None, &None);
+            for input in inputs {
+                let r = policy.run("Expand JSON input", || input.resolve(&ctx)).await
+                    .with_context(|| format!("Could not resolve expand JSON input {:?}", input.value))?;
+
+                result.extend(r);
+            }
+
+            info!("Resolved expand JSON inputs");
+
+            Ok( (result, vec![], vec![]) )
+        },
+        None => Ok( (vec![], vec![], vec![]) )
+    }
+}
+
+async fn resolve_and_wrap_kv2_all_input(ctx: NovopsContext, inputs: Option<Vec<HashiVaultKeyValueV2AllInput>>) -> ResolveOutput {
+
+    match inputs {
+        Some(inputs) => {
+            info!("Resolving Hashivault KV2 all keys inputs");
+
+            let mut result = Vec::new();
+
+            let policy = RetryPolicy::for_input(&ctx, &None, &None);
+            for input in inputs {
+                let r = policy.run("Hashivault KV2 all keys input", || input.resolve(&ctx)).await
+                    .with_context(|| format!("Could not resolve Hashivault KV2 all keys input {:?}", input.path))?;
+
+                result.extend(r);
+            }
+
+            info!("Resolved Hashivault KV2 all keys inputs");
+
+            Ok( (result, vec![], vec![]) )
+        },
+        None => Ok( (vec![], vec![], vec![]) )
+    }
+}
+
+async fn resolve_and_wrap_caller_identity_input(ctx: NovopsContext, input: Option<AwsStsCallerIdentityInput>) -> ResolveOutput {
+
+    match input {
+        Some(input) => {
+            info!("Resolving AWS STS caller identity input");
+
+            let policy = RetryPolicy::for_input(&ctx, &None, &None);
+            let r = policy.run("AWS STS caller identity input", || input.resolve(&ctx)).await
+                .with_context(|| format!("Could not resolve AWS STS caller identity input {:?}", input))?;
+
+            info!("Resolved AWS STS caller identity input");
+
+            Ok( (r, vec![], vec![]) )
+        },
+        None => Ok( (vec![], vec![], vec![]) )
+    }
+}
+
+async fn resolve_and_wrap_hashivault_input(ctx: NovopsContext, hashivault: Option<HashiVaultInput>) -> ResolveOutput {
 
     match hashivault {
         Some(hashivault) => {
 
             info!("Resolving Hashivault inputs");
 
-            let r = hashivault.aws.resolve(&ctx).await
+            let policy = RetryPolicy::for_input(&ctx, &None, &None);
+            let r = policy.run("Hashivault AWS input", || hashivault.aws.resolve(&ctx)).await
                 .with_context(|| format!("Could not resolve Hashivault input {:?}", hashivault))?;
 
             info!("Resolved Hashivault inputs");
 
-            Ok( (r, vec![]) )
+            Ok( (r, vec![], vec![]) )
         },
 
-        None => Ok( (vec![], vec![]) ),
+        None => Ok( (vec![], vec![], vec![]) ),
     }
 
     
 }
 
-async fn resolve_and_wrap_aws_input(ctx: NovopsContext, aws: Option<AwsInput>) -> Result<(Vec<VariableOutput>, Vec<FileOutput>), anyhow::Error> {
+/// Resolve credentials of an `aws_credentials_profiles` profile, generated by exactly one of `aws` or `hashivault`
+async fn resolve_aws_credentials_profile(ctx: &NovopsContext, profile: AwsCredentialsProfileInput) -> Result<AwsProfileCredentials, anyhow::Error> {
+    let nested_options = profile.aws.as_ref().is_some_and(|a| a.use_for_inputs() || a.credentials_profile().is_some())
+        || profile.hashivault.as_ref().is_some_and(|h| h.aws.use_for_inputs.unwrap_or(false) || h.aws.credentials_profile.is_some());
+    if nested_options {
+        return Err(anyhow::anyhow!("`use_for_inputs` and `credentials_profile` can't be set on Inputs of AWS credentials profile '{}'", profile.name));
+    }
+
+    let (output, backend) = match (profile.aws, profile.hashivault) {
+        (Some(aws), None) => {
+            let backend = aws.backend();
+            (resolve_and_wrap_aws_input(ctx.clone(), Some(aws)).await?, backend)
+        },
+        (None, Some(hv)) => (resolve_and_wrap_hashivault_input(ctx.clone(), Some(hv)).await?, None),
+        _ => return Err(anyhow::anyhow!("Exactly one of `aws` or `hashivault` must be set for AWS credentials profile '{}'", profile.name))
+    };
+
+    Ok(AwsProfileCredentials { profile: profile.name, region: backend_region(ctx, &backend)?, credentials: output.0 })
+}
+
+async fn resolve_and_wrap_aws_input(ctx: NovopsContext, aws: Option<AwsInput>) -> ResolveOutput {
 
     match aws {
         Some(aws) => {
             info!("Resolving AWS inputs");
 
-            let vars = aws.assume_role.resolve(&ctx).await
+            // MFA token code is prompted once, outside of retries and timeouts
+            let aws = aws.with_resolved_mfa_token_code(&ctx).await
+                .with_context(|| "Couldn't get MFA token code for AWS input")?;
+
+            let policy = RetryPolicy::for_input(&ctx, &None, &None);
+            let vars = policy.run("AWS input", || aws.resolve(&ctx)).await
                 .with_context(|| format!("Could not resolve AWS input {:?}", aws))?;
 
             info!("Resolved AWS inputs");
 
-            Ok( (vars, vec![]) )
+            Ok( (vars, vec![], vec![]) )
             
         },
-        None => Ok( (vec![], vec![]) ),
+        None => Ok( (vec![], vec![], vec![]) ),
     }
 }
\ No newline at end of file
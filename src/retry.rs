@@ -0,0 +1,275 @@
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+
+use log::warn;
+use rand::Rng;
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::core::NovopsContext;
+
+/// Default maximum attempts to resolve an Input
+const MAX_ATTEMPTS_DEFAULT: u32 = 3;
+
+/// Default delay before first retry (milliseconds)
+const INITIAL_DELAY_DEFAULT: u64 = 200;
+
+/// Default maximum delay between attempts (milliseconds)
+const MAX_DELAY_DEFAULT: u64 = 5000;
+
+/// Retry policy used when an Input fails with a transient error,
+/// such as a throttling error, a 5xx response or a network error.
+#[derive(Debug, Deserialize, Clone, PartialEq, JsonSchema, Default)]
+pub struct RetryConfig {
+    /// Maximum attempts to resolve an Input, including first attempt. Set to 1 to disable retries. Default: 3.
+    pub max_attempts: Option<u32>,
+
+    /// Delay before first retry in milliseconds, doubled after each attempt (exponential backoff). Default: 200.
+    pub initial_delay: Option<u64>,
+
+    /// Maximum delay between attempts in milliseconds. Default: 5000.
+    pub max_delay: Option<u64>,
+
+    /// Wait for a random delay between 0 and computed delay, so that Inputs failing together
+    /// are not retried at the same time. Default: true.
+    pub jitter: Option<bool>,
+
+    /// Maximum total time in milliseconds to resolve an Input, including all attempts and delays between them.
+    /// Resolution fails without further retries once reached. No deadline by default.
+    pub deadline: Option<u64>
+}
+
+impl RetryConfig {
+    /// Merge `other` on top of this config, fields set in `other` taking precedence
+    pub fn merge(self, other: RetryConfig) -> RetryConfig {
+        RetryConfig {
+            max_attempts: other.max_attempts.or(self.max_attempts),
+            initial_delay: other.initial_delay.or(self.initial_delay),
+            max_delay: other.max_delay.or(self.max_delay),
+            jitter: other.jitter.or(self.jitter),
+            deadline: other.deadline.or(self.deadline),
+        }
+    }
+}
+
+/// Marker for transient errors, set by modules on errors which may succeed if retried
+#[derive(Debug)]
+pub struct RetryableError;
+
+impl fmt::Display for RetryableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Transient error")
+    }
+}
+
+/// Mark error as retryable if `retryable` is true
+pub fn retryable_if(err: impl Into<anyhow::Error>, retryable: bool) -> anyhow::Error {
+    let err = err.into();
+    if retryable {
+        err.context(RetryableError)
+    } else {
+        err
+    }
+}
+
+/// Whether error (or one of its causes) is marked as retryable
+pub fn is_retryable(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<RetryableError>().is_some()
+}
+
+/// Whether HTTP status denotes a transient error: 429 Too Many Requests or 5xx (except 501 Not Implemented)
+pub fn is_retryable_status(status: u16) -> bool {
+    status == 429 || (status >= 500 && status != 501)
+}
+
+/// Retry policy and timeout with defaults applied
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    pub jitter: bool,
+
+    /// Timeout for each attempt
+    pub timeout: Option<Duration>,
+
+    /// Maximum total time for all attempts
+    pub deadline: Option<Duration>
+}
+
+impl RetryPolicy {
+
+    /// Policy for an Input: `config.default` retry and timeout, overridden by Input's own settings
+    pub fn for_input(ctx: &NovopsContext, retry: &Option<RetryConfig>, timeout: &Option<u64>) -> RetryPolicy {
+        let defaults = ctx.config.default.clone().unwrap_or_default();
+
+        let retry = defaults.retry.unwrap_or_default()
+            .merge(retry.clone().unwrap_or_default());
+
+        RetryPolicy {
+            max_attempts: retry.max_attempts.unwrap_or(MAX_ATTEMPTS_DEFAULT).max(1),
+            initial_delay: Duration::from_millis(retry.initial_delay.unwrap_or(INITIAL_DELAY_DEFAULT)),
+            max_delay: Duration::from_millis(retry.max_delay.unwrap_or(MAX_DELAY_DEFAULT)),
+            jitter: retry.jitter.unwrap_or(true),
+            timeout: timeout.or(defaults.timeout).map(Duration::from_secs),
+            deadline: retry.deadline.map(Duration::from_millis)
+        }
+    }
+
+    /// Delay before retrying after given attempt (starting at 1)
+    pub fn delay_after(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let delay = self.initial_delay.saturating_mul(factor).min(self.max_delay);
+
+        if self.jitter {
+            let millis = delay.as_millis() as u64;
+            Duration::from_millis(rand::thread_rng().gen_range(0, millis + 1))
+        } else {
+            delay
+        }
+    }
+
+    /**
+     * Run `f` until it succeeds, fails with a non-retryable error or max attempts are reached.
+     * Each attempt is cancelled if it doesn't complete before timeout, which is considered retryable.
+     * Current attempt is cancelled and no more attempts are made once deadline is reached.
+     */
+    pub async fn run<T, F, Fut>(&self, description: &str, f: F) -> Result<T, anyhow::Error>
+    where F: Fn() -> Fut, Fut: Future<Output = Result<T, anyhow::Error>> {
+
+        match self.deadline {
+            Some(deadline) => tokio::time::timeout(deadline, self.run_attempts(description, f)).await
+                .unwrap_or_else(|_| Err(anyhow::anyhow!("Deadline of {:?} exceeded resolving {}", deadline, description))),
+            None => self.run_attempts(description, f).await
+        }
+    }
+
+    async fn run_attempts<T, F, Fut>(&self, description: &str, f: F) -> Result<T, anyhow::Error>
+    where F: Fn() -> Fut, Fut: Future<Output = Result<T, anyhow::Error>> {
+
+        let mut attempt = 1;
+        loop {
+            let result = match self.timeout {
+                Some(timeout) => match tokio::time::timeout(timeout, f()).await {
+                    Ok(r) => r,
+                    Err(_) => Err(retryable_if(anyhow::anyhow!("Timed out after {:?} resolving {}", timeout, description), true)),
+                },
+                None => f().await
+            };
+
+            match result {
+                Ok(r) => return Ok(r),
+                Err(e) if attempt < self.max_attempts && is_retryable(&e) => {
+                    let delay = self.delay_after(attempt);
+                    warn!("Attempt {}/{} to resolve {} failed, retrying in {:?}: {:#}", attempt, self.max_attempts, description, delay, e);
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                },
+                Err(e) if attempt > 1 => return Err(e.context(format!("Failed to resolve {} after {} attempts", description, attempt))),
+                Err(e) => return Err(e)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn no_delay_policy(max_attempts: u32, timeout: Option<Duration>) -> RetryPolicy {
+        RetryPolicy { max_attempts, initial_delay: Duration::ZERO, max_delay: Duration::ZERO, jitter: false, timeout, deadline: None }
+    }
+
+    #[test]
+    fn test_delay_after() {
+        let policy = RetryPolicy { 
+            max_attempts: 5, 
+            initial_delay: Duration::from_millis(100), 
+            max_delay: Duration::from_millis(300), 
+            jitter: false, 
+            timeout: None,
+            deadline: None
+        };
+        assert_eq!(policy.delay_after(1), Duration::from_millis(100));
+        assert_eq!(policy.delay_after(2), Duration::from_millis(200));
+        assert_eq!(policy.delay_after(3), Duration::from_millis(300));
+        assert_eq!(policy.delay_after(40), Duration::from_millis(300));
+
+        let jitter = RetryPolicy { jitter: true, ..policy };
+        assert!(jitter.delay_after(2) <= Duration::from_millis(200));
+    }
+
+    #[tokio::test]
+    async fn test_run_retries_transient_errors() {
+        let attempts = AtomicU32::new(0);
+        let result = no_delay_policy(3, None).run("test input", || async {
+            match attempts.fetch_add(1, Ordering::SeqCst) {
+                0 | 1 => Err(retryable_if(anyhow::anyhow!("throttled"), true)),
+                _ => Ok("ok")
+            }
+        }).await;
+        assert_eq!(result.unwrap(), "ok");
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+
+        // non-retryable error fails immediately
+        let attempts = AtomicU32::new(0);
+        let result: Result<(), _> = no_delay_policy(3, None).run("test input", || async {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err(retryable_if(anyhow::anyhow!("not found"), false))
+        }).await;
+        assert!(result.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+
+        // transient error fails once max attempts is reached
+        let attempts = AtomicU32::new(0);
+        let result: Result<(), _> = no_delay_policy(2, None).run("test input", || async {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err(anyhow::anyhow!("unavailable").context(RetryableError).context("Couldn't read secret"))
+        }).await;
+        assert!(format!("{:?}", result.unwrap_err()).contains("Failed to resolve test input after 2 attempts"));
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_run_timeout() {
+        let attempts = AtomicU32::new(0);
+        let result = no_delay_policy(2, Some(Duration::from_millis(10))).run("test input", || async {
+            if attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+                tokio::time::sleep(Duration::from_secs(10)).await;
+            }
+            Ok("ok")
+        }).await;
+        assert_eq!(result.unwrap(), "ok");
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+
+        let result: Result<(), _> = no_delay_policy(1, Some(Duration::from_millis(10))).run("test input", || async {
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            Ok(())
+        }).await;
+        assert!(format!("{:?}", result.unwrap_err()).contains("Timed out after 10ms resolving test input"));
+    }
+
+    #[tokio::test]
+    async fn test_run_deadline() {
+        let attempts = AtomicU32::new(0);
+        let policy = RetryPolicy { deadline: Some(Duration::from_millis(50)), ..no_delay_policy(100, Some(Duration::from_millis(20))) };
+        let result: Result<(), _> = policy.run("test input", || async {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            Ok(())
+        }).await;
+
+        assert!(format!("{:?}", result.unwrap_err()).contains("Deadline of 50ms exceeded resolving test input"));
+        assert!(attempts.load(Ordering::SeqCst) < 100);
+    }
+
+    #[test]
+    fn test_retryable_status() {
+        assert!(is_retryable_status(429));
+        assert!(is_retryable_status(503));
+        assert!(!is_retryable_status(501));
+        assert!(!is_retryable_status(404));
+    }
+}
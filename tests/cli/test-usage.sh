@@ -31,3 +31,4 @@ $novops_test_cmd list environments -c tests/.novops.multi-env.yml | grep preprod
 $novops_test_cmd list environments -c tests/.novops.multi-env.yml -o json | grep '"preprod"'
 $novops_test_cmd list outputs -e dev -c tests/.novops.multi-env.yml | grep MY_APP_HOST
 $novops_test_cmd list outputs -e dev -c tests/.novops.multi-env.yml -o json | grep '"MY_APP_HOST"'
+$novops_test_cmd list outputs -e declared -c tests/.novops.optional.yml -o json | grep '"optional":\["SENTRY_DSN","EXTRA_CONFIG"\]'
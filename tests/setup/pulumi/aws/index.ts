@@ -14,6 +14,24 @@ const ssmParamSecureString = new aws.ssm.Parameter("novops-test-ssm-param-secure
     value: "novops-string-test-secure",
 })
 
+const ssmParamPathDbHost = new aws.ssm.Parameter("novops-test-ssm-path-db-host", {
+    name: "/novops-test/app/db/host",
+    type: "String",
+    value: "db.novops.test",
+})
+
+const ssmParamPathDbPassword = new aws.ssm.Parameter("novops-test-ssm-path-db-password", {
+    name: "/novops-test/app/db/password",
+    type: "SecureString",
+    value: "novops-db-password",
+})
+
+const ssmParamPathApiKey = new aws.ssm.Parameter("novops-test-ssm-path-api-key", {
+    name: "/novops-test/app/api-key",
+    type: "SecureString",
+    value: "novops-api-key",
+})
+
 // Secret Manager
 const secretManagerSecretString = new aws.secretsmanager.Secret(`novops-test-secretsmanager-string`, {
     name: "novops-test-secretsmanager-string",
@@ -69,4 +87,10 @@ new aws.s3.BucketObject("file-object", {
     bucket: bucket.bucket,
     key: "path/to/file",
     source: new pulumi.asset.StringAsset("file-content"),
+})
+
+new aws.s3.BucketObject("binary-object", {
+    bucket: bucket.bucket,
+    key: "path/to/binary",
+    contentBase64: "8J+Slv8=", // 💖 emoji followed by non-UTF-8 byte [240, 159, 146, 150, 255]
 })
\ No newline at end of file
@@ -22,6 +22,24 @@ const kv2Secret = new vault.kv.SecretV2("kv2Secret", {
     }),
 })
 
+// Secrets listed by hvault_kv2_all, nested folder being ignored
+const kv2FolderSecret = new vault.kv.SecretV2("kv2FolderSecret", {
+    mount: kv2Engine.path,
+    name: "test_hashivault_kv2_folder/app",
+    dataJson: JSON.stringify({
+        user: "novops",
+        "db-password": "s3cret_kv2_folder",
+    }),
+})
+
+const kv2NestedFolderSecret = new vault.kv.SecretV2("kv2NestedFolderSecret", {
+    mount: kv2Engine.path,
+    name: "test_hashivault_kv2_folder/nested/other",
+    dataJson: JSON.stringify({
+        ignored: "ignored",
+    }),
+})
+
 // KV1
 const kv1Engine = new vault.Mount("kv1Engine", {
     type: "kv",
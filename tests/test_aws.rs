@@ -1,7 +1,7 @@
 pub mod test_lib;
 
 use chrono::Utc;
-use test_lib::{load_env_for, test_setup};
+use test_lib::{load_env_for, test_setup, aws_ensure_ssm_parameter_versions};
 use log::info;
 
 #[tokio::test]
@@ -21,6 +21,37 @@ async fn test_assume_role() -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_assume_role_chain() -> Result<(), anyhow::Error> {
+
+    test_setup().await?;
+
+    let outputs = load_env_for("aws_assumerole", "chain").await?;
+
+    info!("test_assume_role_chain: Found variables: {:?}", outputs.variables);
+
+    assert!(!outputs.variables.get("AWS_ACCESS_KEY_ID").unwrap().value.is_empty());
+    assert!(!outputs.variables.get("AWS_SESSION_TOKEN").unwrap().value.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_assume_role_session_options() -> Result<(), anyhow::Error> {
+
+    test_setup().await?;
+
+    std::env::set_var("NOVOPS_TEST_MFA_TOKEN_CODE", "123456");
+    let outputs = load_env_for("aws_assumerole", "session-options").await?;
+
+    info!("test_assume_role_session_options: Found variables: {:?}", outputs.variables);
+
+    assert!(!outputs.variables.get("AWS_ACCESS_KEY_ID").unwrap().value.is_empty());
+    assert!(!outputs.variables.get("AWS_SESSION_TOKEN").unwrap().value.is_empty());
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_assume_role_duration() -> Result<(), anyhow::Error> {
 
@@ -95,6 +126,21 @@ async fn test_assume_role_identity_cache_load_timeout_error() -> Result<(), anyh
     Ok(())
 }
 
+#[tokio::test]
+async fn test_assume_role_use_for_inputs() -> Result<(), anyhow::Error> {
+
+    test_setup().await?;
+
+    let outputs = load_env_for("aws_assumerole_inputs", "dev").await?;
+
+    info!("test_assume_role_use_for_inputs: Found variables: {:?}", outputs.variables);
+
+    assert!(!outputs.variables.get("AWS_ACCESS_KEY_ID").unwrap().value.is_empty());
+    assert_eq!(outputs.variables.get("SSM_PARAM_STORE_TEST_STRING").unwrap().value, "novops-test-ssm-param-string");
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_ssm_param() -> Result<(), anyhow::Error> {
 
@@ -115,6 +161,52 @@ async fn test_ssm_param() -> Result<(), anyhow::Error> {
 
 }
 
+#[tokio::test]
+async fn test_ssm_param_versions() -> Result<(), anyhow::Error> {
+
+    test_setup().await?;
+    aws_ensure_ssm_parameter_versions("novops-test-ssm-param-versioned", &["value-v1", "value-v2"]).await?;
+
+    // both are read with a single batch request, pinned version must not be used for latest
+    let outputs = load_env_for("aws_ssm", "versions").await?;
+
+    assert_eq!(outputs.variables.get("SSM_PARAM_LATEST").unwrap().value, "value-v2");
+    assert_eq!(outputs.variables.get("SSM_PARAM_V1").unwrap().value, "value-v1");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ssm_parameters_by_path() -> Result<(), anyhow::Error> {
+
+    test_setup().await?;
+
+    let outputs = load_env_for("aws_ssm", "by-path").await?;
+
+    assert_eq!(outputs.variables.get("DB_HOST").unwrap().value, "db.novops.test");
+    assert_eq!(outputs.variables.get("DATABASE_PASSWORD").unwrap().value, "novops-db-password");
+    assert_eq!(outputs.variables.get("API_KEY").unwrap().value, "novops-api-key");
+    assert_eq!(outputs.variables.get("/novops-test/app/api-key").unwrap().value, "novops-api-key");
+    assert!(!outputs.variables.contains_key("/novops-test/app/db/host"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sts_caller_identity() -> Result<(), anyhow::Error> {
+
+    test_setup().await?;
+
+    let outputs = load_env_for("aws_caller_identity", "renamed").await?;
+
+    // LocalStack default account
+    assert_eq!(outputs.variables.get("ACCOUNT_ID").unwrap().value, "000000000000");
+    assert!(outputs.variables.get("CALLER_ARN").unwrap().value.starts_with("arn:aws:"));
+    assert_eq!(outputs.variables.get("DEPLOY_REGION").unwrap().value, "eu-west-3");
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_secretsmanager() -> Result<(), anyhow::Error> {
 
@@ -144,6 +236,7 @@ async fn test_s3_object() -> Result<(), anyhow::Error> {
     let outputs = load_env_for("aws_s3_object", "dev").await?;
     assert_eq!(outputs.variables.get("S3_OBJECT_AS_VAR").unwrap().value, "variable-content");
     assert_eq!(outputs.files.get("/tmp/S3_OBJECT_AS_FILE").unwrap().content, "file-content".as_bytes());
+    assert_eq!(outputs.files.get("/tmp/S3_OBJECT_AS_BINARY_FILE").unwrap().content, vec![240, 159, 146, 150, 255]);
 
     Ok(())
 }
@@ -4,8 +4,15 @@ use novops::modules::files::FileOutput;
 use novops::modules::variables::VariableOutput;
 use novops::{check_working_dir_permissions, export_file_outputs, get_config_file_path, 
     list_environments, list_outputs_for_environment, load_environment_write_vars, 
-    make_context, prepare_exec_command, should_error_tty, NovopsLoadArgs};
-use novops::core::{NovopsContext, NovopsConfig, NovopsConfigFile, NovopsConfigDefault, NovopsEnvironmentInput};
+    make_context, get_current_environment, prepare_exec_command, should_error_tty, resolve_aws_credential_process, NovopsLoadArgs};
+use novops::core::{NovopsContext, NovopsConfig, NovopsConfigFile, NovopsConfigDefault, NovopsEnvironmentInput, StringResolvableInput, BytesResolvableInput};
+use novops::clients::ClientRegistry;
+use novops::retry::RetryPolicy;
+use novops::resolve::{FallbackOutcome, DeclaredFallbacks};
+use novops::modules::aws::client::session_credentials_from_outputs;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use aws_smithy_types::DateTime;
+use aws_smithy_types::date_time::Format;
 use std::collections::HashMap;
 use std::ffi::OsStr;
 use std::path::PathBuf;
@@ -28,7 +35,7 @@ async fn test_load_simple_config() -> Result<(), anyhow::Error>{
     let workdir = clean_and_setup_test_dir("test_load_simple_config")?;
 
     let args = NovopsLoadArgs {
-        config: Some(String::from(CONFIG_EMPTY)),
+        config: vec![String::from(CONFIG_EMPTY)],
         env: Some(String::from("dev")),
         working_directory: Some(workdir.clone().into_os_string().into_string().unwrap()),
         skip_working_directory_check: Some(false),
@@ -45,25 +52,50 @@ async fn test_load_simple_config() -> Result<(), anyhow::Error>{
             workdir: workdir.clone(),
             config_file_data: NovopsConfigFile{
                 name: Some(String::from("test-empty")),
+                include: None,
                 environments: HashMap::from([
                     (String::from("dev"), NovopsEnvironmentInput {
+                        extends: None,
+                        is_abstract: None,
                         variables: None,
                         files: None,
                         aws: None,
                         hashivault: None,
                         sops_dotenv: None,
+                        aws_ssm_parameters_by_path: None,
+                        expand_json: None,
+                        hvault_kv2_all: None,
+                        aws_sts_caller_identity: None,
+                        aws_credentials_profiles: None,
+                        config: None,
                     })
                 ]),
                 config: Some(NovopsConfig { 
                     default: Some(NovopsConfigDefault {
-                            environment: Some(String::from("dev"))
+                            environment: Some(String::from("dev")),
+                            retry: None,
+                            timeout: None
                     }), 
                     hashivault: None,
-                    aws: None
+                    aws: None,
+                    backends: None
                 })
             },
+            config: NovopsConfig { 
+                default: Some(NovopsConfigDefault {
+                        environment: Some(String::from("dev")),
+                        retry: None,
+                        timeout: None
+                }), 
+                hashivault: None,
+                aws: None,
+                backends: None
+            },
             env_var_filepath: workdir.join("vars"),
-            dry_run: false
+            dry_run: false,
+            resolved_variables: HashMap::new(),
+            clients: ClientRegistry::default(),
+            aws_session_credentials: None
         }
     );
 
@@ -83,7 +115,7 @@ async fn test_simple_run() -> Result<(), anyhow::Error>{
     let workdir = clean_and_setup_test_dir("test_simple_run")?;
 
     load_environment_write_vars(&NovopsLoadArgs { 
-            config: Some(String::from(CONFIG_STANDALONE)),
+            config: vec![String::from(CONFIG_STANDALONE)],
             env: Some(String::from("simple-test")), 
             working_directory: Some(workdir.clone().into_os_string().into_string().unwrap()),
             skip_working_directory_check: Some(false),
@@ -136,7 +168,7 @@ async fn test_symlink_flag() -> Result<(), anyhow::Error> {
 
     let expect_symlink_at = PathBuf::from(TEST_DIR).join("test-symlink");
     load_environment_write_vars(&NovopsLoadArgs { 
-            config: Some(String::from(CONFIG_STANDALONE)),
+            config: vec![String::from(CONFIG_STANDALONE)],
             env: Some(String::from("symlink-test")),
             working_directory: Some(workdir.clone().into_os_string().into_string().unwrap()),
             skip_working_directory_check: Some(false),
@@ -158,7 +190,7 @@ async fn test_symlink_flag() -> Result<(), anyhow::Error> {
     // expect existing symlink to be overriden
     let workdir_override = clean_and_setup_test_dir("test_symlink_flag_override")?;
     load_environment_write_vars(&NovopsLoadArgs { 
-            config: Some(String::from(CONFIG_STANDALONE)),
+            config: vec![String::from(CONFIG_STANDALONE)],
             env: Some(String::from("symlink-test-2")),
             working_directory: Some(workdir_override.clone().into_os_string().into_string().unwrap()), 
             skip_working_directory_check: Some(false),
@@ -190,7 +222,7 @@ async fn test_symlink_no_file_override() -> Result<(), anyhow::Error> {
     
     // expect error as we cannot erase existing file
     let result = load_environment_write_vars(&NovopsLoadArgs { 
-            config: Some(String::from(CONFIG_STANDALONE)),
+            config: vec![String::from(CONFIG_STANDALONE)],
             env: Some(String::from("symlink-override-test")), 
             working_directory: Some(workdir.clone().into_os_string().into_string().unwrap()),
             skip_working_directory_check: Some(false),
@@ -307,7 +339,7 @@ async fn test_default_loaded_vars() -> Result<(), anyhow::Error> {
 async fn test_list_environments() -> Result<(), anyhow::Error> {
     test_setup().await?;
 
-    let result = list_environments(Some(String::from("tests/.novops.multi-env.yml"))).await?;
+    let result = list_environments(vec![String::from("tests/.novops.multi-env.yml")]).await?;
 
     assert_eq!(result.len(), 4);
     assert_eq!(result[0], "dev");
@@ -321,7 +353,7 @@ async fn test_list_environments() -> Result<(), anyhow::Error> {
 async fn test_list_environment_output() -> Result<(), anyhow::Error> {
     test_setup().await?;
 
-    let result = list_outputs_for_environment(Some(String::from("tests/.novops.multi-env.yml")), Some("dev".to_string())).await?;
+    let result = list_outputs_for_environment(vec![String::from("tests/.novops.multi-env.yml")], Some("dev".to_string())).await?;
 
     // Assert this
     assert_eq!(result.variables.len(), 3);
@@ -467,4 +499,787 @@ async fn test_export_file_outputs() -> Result<(), anyhow::Error> {
     assert!(result_err.is_err());
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn test_environment_extends() -> Result<(), anyhow::Error> {
+    test_setup().await?;
+
+    let result = load_env_dryrun_for("extends", "prod").await?;
+
+    // overridden by prod
+    assert_eq!(result.variables.get("APP_HOST").unwrap().value, "app.co");
+    // inherited from base
+    assert_eq!(result.variables.get("APP_LOG_LEVEL").unwrap().value, "info");
+    // inherited from base-aws, overriding base
+    assert_eq!(result.variables.get("APP_REGION").unwrap().value, "eu-west-3");
+    // prod only
+    assert_eq!(result.variables.get("APP_REPLICAS").unwrap().value, "3");
+    
+    // aws input inherited from base-aws
+    assert!(!result.variables.get("AWS_ACCESS_KEY_ID").unwrap().value.is_empty());
+
+    // APP_CONFIG file overridden by prod, APP_CA file inherited from base
+    let file_content_for = |var: &str| result.files.values()
+        .find(|f| f.variable.name == var)
+        .map(|f| f.content.clone());
+    
+    assert_eq!(result.files.len(), 2);
+    assert_eq!(file_content_for("APP_CONFIG").unwrap(), b"prod-config");
+    assert_eq!(file_content_for("APP_CA").unwrap(), b"base-ca");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_environment_extends_errors() -> Result<(), anyhow::Error> {
+    test_setup().await?;
+
+    let result_abstract = load_env_dryrun_for("extends", "base").await;
+    assert!(result_abstract.is_err(), "Loading an abstract environment should fail, got {:?}", result_abstract);
+
+    let result_cycle = load_env_dryrun_for("extends", "cycle-a").await;
+    assert!(format!("{:?}", result_cycle.expect_err("Expected cyclic extends error")).contains("cycle-a -> cycle-b -> cycle-a"));
+
+    let result_unknown = load_env_dryrun_for("extends", "unknown-parent").await;
+    assert!(result_unknown.is_err(), "Extending an unknown environment should fail, got {:?}", result_unknown);
+
+    // abstract environments are not listed
+    let envs = list_environments(vec![String::from("tests/.novops.extends.yml")]).await?;
+    assert_eq!(envs, vec!["cycle-a", "cycle-b", "dev", "diamond", "prod", "unknown-parent"]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_environment_extends_diamond() -> Result<(), anyhow::Error> {
+    test_setup().await?;
+
+    let result = load_env_dryrun_for("extends", "diamond").await?;
+    assert_eq!(result.variables.get("LEFT").unwrap().value, "left");
+    assert_eq!(result.variables.get("RIGHT").unwrap().value, "right");
+
+    // base-sops Inputs inherited through both parents are kept once
+    let env = get_current_environment(&result.context).await?;
+    assert_eq!(env.sops_dotenv.unwrap().len(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_environment_merge_files() -> Result<(), anyhow::Error> {
+    let env = |yaml: &str| serde_yaml::from_str::<NovopsEnvironmentInput>(yaml);
+
+    // files are merged by name if no variable, symlink or dest is set
+    let base = env("files: [ { name: first, content: a }, { name: second, content: b }, { content: c } ]")?;
+    let other = env("files: [ { name: second, content: b2 }, { name: third, content: d }, { content: e } ]")?;
+
+    let files = base.merge(other).files.unwrap();
+    let names: Vec<Option<String>> = files.iter().map(|f| f.name.clone()).collect();
+    assert_eq!(names, vec![Some(String::from("first")), Some(String::from("second")), None, Some(String::from("third")), None]);
+    assert_eq!(files[1].content, BytesResolvableInput::StringResolvableInput(StringResolvableInput::String(String::from("b2"))));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_config_include() -> Result<(), anyhow::Error> {
+    test_setup().await?;
+
+    let result = load_env_dryrun_for("include", "dev").await?;
+    let config = result.context.config_file_data.config.clone().unwrap();
+
+    // including file takes precedence
+    assert_eq!(result.context.config_file_data.name, Some(String::from("test-include")));
+    assert_eq!(config.hashivault.unwrap().address, Some(String::from("http://localhost:8200")));
+    assert_eq!(config.aws.unwrap().region, Some(String::from("eu-west-3")));
+
+    // environment merged from including file and included files
+    assert_eq!(result.variables.get("APP_HOST").unwrap().value, "localhost");
+    assert_eq!(result.variables.get("ORG_NAME").unwrap().value, "crafteo");
+    assert_eq!(result.files.len(), 1);
+
+    let envs = list_environments(vec![String::from("tests/.novops.include.yml")]).await?;
+    assert_eq!(envs, vec!["dev"]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_config_multiple_files() -> Result<(), anyhow::Error> {
+    test_setup().await?;
+
+    let config_files = vec![
+        String::from("tests/.novops.include.yml"), 
+        String::from("tests/.novops.include-override.yml")
+    ];
+
+    let envs = list_environments(config_files.clone()).await?;
+    assert_eq!(envs, vec!["dev", "prod"]);
+
+    let result = list_outputs_for_environment(config_files, Some(String::from("prod"))).await?;
+    let config = result.context.config_file_data.config.clone().unwrap();
+    
+    // later file takes precedence
+    assert_eq!(config.aws.unwrap().region, Some(String::from("us-east-1")));
+    assert_eq!(config.hashivault.unwrap().address, Some(String::from("http://localhost:8200")));
+    assert_eq!(result.variables.get("APP_HOST").unwrap().value, "app.co");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_config_include_errors() -> Result<(), anyhow::Error> {
+    test_setup().await?;
+
+    let dir = tempdir()?;
+
+    // include cycle
+    let cycle_a = dir.path().join("cycle-a.yml");
+    fs::write(&cycle_a, "include: [ cycle-b.yml ]\nenvironments: {}")?;
+    fs::write(dir.path().join("cycle-b.yml"), "include: [ cycle-a.yml ]\nenvironments: {}")?;
+
+    let result_cycle = list_environments(vec![cycle_a.to_str().unwrap().to_string()]).await;
+    let err_cycle = format!("{:?}", result_cycle.expect_err("Expected include cycle error"));
+    assert!(err_cycle.contains("includes itself"), "Unexpected error: {}", err_cycle);
+
+    // conflicting included files
+    let conflict = dir.path().join("conflict.yml");
+    fs::write(&conflict, "include: [ 'region-*.yml' ]")?;
+    fs::write(dir.path().join("region-1.yml"), "config:\n  aws:\n    region: eu-west-1")?;
+    fs::write(dir.path().join("region-2.yml"), "config:\n  aws:\n    region: eu-west-3")?;
+
+    let result_conflict = list_environments(vec![conflict.to_str().unwrap().to_string()]).await;
+    let err_conflict = format!("{:?}", result_conflict.expect_err("Expected conflict error"));
+    assert!(err_conflict.contains("config.aws.region") && err_conflict.contains("region-1.yml") && err_conflict.contains("region-2.yml"), 
+        "Unexpected error: {}", err_conflict);
+
+    // missing included file
+    let missing = dir.path().join("missing.yml");
+    fs::write(&missing, "include: [ does-not-exist.yml ]")?;
+    
+    let result_missing = list_environments(vec![missing.to_str().unwrap().to_string()]).await;
+    assert!(result_missing.is_err(), "Expected error for missing included file");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_template_variables() -> Result<(), anyhow::Error> {
+    test_setup().await?;
+
+    let result = load_env_dryrun_for("template", "dev").await?;
+
+    assert_eq!(result.variables.get("DB_HOST").unwrap().value, "db.example.com");
+    assert_eq!(result.variables.get("DATABASE_URL").unwrap().value, 
+        "postgres://novops:RESULT:app/db/password@db.example.com/db?cost=${NOT_A_VAR}");
+
+    let db_config = result.files.values().find(|f| f.variable.name == "DB_CONFIG").unwrap();
+    assert_eq!(db_config.content, 
+        b"url: postgres://novops:RESULT:app/db/password@db.example.com/db?cost=${NOT_A_VAR}");
+
+    let result_cycle = load_env_dryrun_for("template", "cycle").await;
+    let err_cycle = format!("{:?}", result_cycle.expect_err("Expected cycle error"));
+    assert!(err_cycle.contains(r#"["BAR", "FOO"] reference each other in a cycle"#), "Unexpected error: {}", err_cycle);
+
+    let result_unknown = load_env_dryrun_for("template", "unknown").await;
+    let err_unknown = format!("{:?}", result_unknown.expect_err("Expected unknown reference error"));
+    assert!(err_unknown.contains(r#"references unknown variable(s) ["DOES_NOT_EXIST"]"#), "Unexpected error: {}", err_unknown);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_parameterized_inputs() -> Result<(), anyhow::Error> {
+    test_setup().await?;
+
+    std::env::set_var("NOVOPS_TEST_PARAMETERIZED_TEAM", "backend");
+
+    let result = load_env_dryrun_for("parameterized", "dev").await?;
+
+    assert_eq!(result.variables.get("DB_PASSWORD").unwrap().value, "RESULT:RESULT:/app/db-secret-path/password");
+    assert_eq!(result.variables.get("API_TOKEN").unwrap().value, "RESULT:dev/backend/api-token");
+    assert_eq!(result.variables.get("AZ_SECRET").unwrap().value, "RESULT:my-vault/app-secret/");
+
+    let s3_file = result.files.values().find(|f| f.variable.name == "S3_FILE").unwrap();
+    assert_eq!(s3_file.content, b"dummy");
+
+    let result_unknown = load_env_dryrun_for("parameterized", "unknown").await;
+    let err_unknown = format!("{:?}", result_unknown.expect_err("Expected unknown reference error"));
+    assert!(err_unknown.contains(r#"references unknown variable(s) ["DOES_NOT_EXIST"]"#), "Unexpected error: {}", err_unknown);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_environment_config_overrides() -> Result<(), anyhow::Error> {
+    test_setup().await?;
+
+    let result_dev = load_env_dryrun_for("env-config", "dev").await?;
+    let dev_hv = result_dev.context.config.hashivault.unwrap();
+    assert_eq!(dev_hv.address, Some(String::from("http://localhost:8200")));
+    assert_eq!(dev_hv.namespace, None);
+    assert_eq!(result_dev.context.config.aws.unwrap().profile, None);
+
+    let result_prod = load_env_dryrun_for("env-config", "prod").await?;
+    let prod_hv = result_prod.context.config.hashivault.unwrap();
+    assert_eq!(prod_hv.address, Some(String::from("https://vault.prod.example.com:8200")));
+    assert_eq!(prod_hv.namespace, Some(String::from("prod")));
+    assert_eq!(prod_hv.verify, Some(false));
+    let prod_aws = result_prod.context.config.aws.unwrap();
+    assert_eq!(prod_aws.profile, Some(String::from("prod")));
+    assert_eq!(prod_aws.region, Some(String::from("eu-west-3")));
+
+    // global config is left untouched
+    let global_hv = result_prod.context.config_file_data.config.unwrap().hashivault.unwrap();
+    assert_eq!(global_hv.address, Some(String::from("http://localhost:8200")));
+
+    let result_prod_us = load_env_dryrun_for("env-config", "prod-us").await?;
+    assert_eq!(result_prod_us.context.config.hashivault.unwrap().namespace, Some(String::from("prod")));
+    let prod_us_aws = result_prod_us.context.config.aws.unwrap();
+    assert_eq!(prod_us_aws.profile, Some(String::from("prod")));
+    assert_eq!(prod_us_aws.region, Some(String::from("us-east-1")));
+    assert_eq!(result_prod_us.variables.get("FOO").unwrap().value, "bar");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_named_backends() -> Result<(), anyhow::Error> {
+    test_setup().await?;
+
+    let result = load_env_dryrun_for("backends", "dev").await?;
+
+    assert_eq!(result.variables.get("PLATFORM_SECRET").unwrap().value, "RESULT:app/db/password");
+    assert_eq!(result.variables.get("TEAM_SECRET").unwrap().value, "RESULT:app/api/token");
+    assert_eq!(result.variables.get("DEFAULT_SECRET").unwrap().value, "RESULT:app/default/password");
+    assert_eq!(result.variables.get("SHARED_PARAM").unwrap().value, "RESULT:/shared/param");
+    assert_eq!(result.variables.get("SHARED_SECRET").unwrap().value, "RESULT:shared-secret");
+    assert_eq!(result.variables.get("AWS_ACCESS_KEY_ID").unwrap().value, "AKIADRYRUNACCESSKEY");
+
+    // Clients are built from named backend config, or default config if no backend is set
+    let platform = novops::modules::hashivault::client::get_backend_config(&result.context, &Some(String::from("platform")))?;
+    assert_eq!(platform.address, Some(String::from("https://vault.platform.example.com:8200")));
+    assert_eq!(platform.namespace, Some(String::from("platform")));
+
+    let default = novops::modules::hashivault::client::get_backend_config(&result.context, &None)?;
+    assert_eq!(default.address, Some(String::from("http://localhost:8200")));
+
+    let shared = novops::modules::aws::client::build_mutable_client_config_from_context(&result.context, &Some(String::from("shared")))?;
+    assert_eq!(shared.profile, Some(String::from("shared-services")));
+    assert_eq!(shared.region, Some(String::from("us-east-1")));
+
+    let default_aws = novops::modules::aws::client::build_mutable_client_config_from_context(&result.context, &None)?;
+    assert_eq!(default_aws.profile, None);
+    assert_eq!(default_aws.region, Some(String::from("eu-west-3")));
+
+    let result_unknown = load_env_dryrun_for("backends", "unknown").await;
+    let err_unknown = format!("{:?}", result_unknown.expect_err("Expected unknown backend error"));
+    assert!(err_unknown.contains(r#"Hashivault backend 'does-not-exist' not found in config. Available backends: ["platform", "team"]"#), 
+        "Unexpected error: {}", err_unknown);
+
+    // Environment overrides are merged with backends of the same name or added
+    let result_override = load_env_dryrun_for("backends", "override").await?;
+    assert_eq!(result_override.variables.get("AWS_ACCESS_KEY_ID").unwrap().value, "AKIADRYRUNDRYUNDRYRUN");
+
+    let shared_override = novops::modules::aws::client::build_mutable_client_config_from_context(&result_override.context, &Some(String::from("shared")))?;
+    assert_eq!(shared_override.profile, Some(String::from("shared-services")));
+    assert_eq!(shared_override.region, Some(String::from("eu-central-1")));
+
+    let team_aws = novops::modules::aws::client::build_mutable_client_config_from_context(&result_override.context, &Some(String::from("team-aws")))?;
+    assert_eq!(team_aws.profile, Some(String::from("team")));
+
+    let platform_override = novops::modules::hashivault::client::get_backend_config(&result_override.context, &Some(String::from("platform")))?;
+    assert_eq!(platform_override.address, Some(String::from("https://vault.platform.example.com:8200")));
+
+    // Assumed role uses named backend
+    let err_assume_role = format!("{:?}", load_env_dryrun_for("backends", "unknown-assume-role").await.expect_err("Expected unknown backend error"));
+    assert!(err_assume_role.contains(r#"AWS backend 'does-not-exist' not found in config. Available backends: ["shared"]"#), 
+        "Unexpected error: {}", err_assume_role);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_shared_clients() -> Result<(), anyhow::Error> {
+    test_setup().await?;
+
+    let result = load_env_dryrun_for("backends", "dev").await?;
+    let ctx = result.context;
+
+    // Clients are built once per backend and shared by Inputs and context clones
+    let platform = novops::modules::hashivault::client::get_client(&ctx, &Some(String::from("platform"))).await?;
+    let platform_again = novops::modules::hashivault::client::get_client(&ctx.clone(), &Some(String::from("platform"))).await?;
+    let default_hv = novops::modules::hashivault::client::get_client(&ctx, &None).await?;
+    assert!(std::sync::Arc::ptr_eq(&platform, &platform_again));
+    assert!(!std::sync::Arc::ptr_eq(&platform, &default_hv));
+
+    let shared_aws = novops::modules::aws::client::get_client(&ctx, &Some(String::from("shared"))).await?;
+    let shared_aws_again = novops::modules::aws::client::get_client(&ctx, &Some(String::from("shared"))).await?;
+    let default_aws = novops::modules::aws::client::get_client(&ctx, &None).await?;
+    assert!(std::sync::Arc::ptr_eq(&shared_aws, &shared_aws_again));
+    assert!(!std::sync::Arc::ptr_eq(&shared_aws, &default_aws));
+
+    // A new load uses new clients
+    let mut other_ctx = ctx.clone();
+    other_ctx.clients = ClientRegistry::default();
+    let platform_other = novops::modules::hashivault::client::get_client(&other_ctx, &Some(String::from("platform"))).await?;
+    assert!(!std::sync::Arc::ptr_eq(&platform, &platform_other));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_retry_config() -> Result<(), anyhow::Error> {
+    test_setup().await?;
+
+    let result = load_env_dryrun_for("retry", "dev").await?;
+    assert_eq!(result.variables.get("CUSTOM_RETRY").unwrap().value, "bar");
+
+    let ctx = &result.context;
+    let env = ctx.config_file_data.environments.get("dev").unwrap();
+    let variables = env.variables.clone().unwrap();
+    let files = env.files.clone().unwrap();
+
+    // Default policy from config.default
+    let default_policy = RetryPolicy::for_input(ctx, &variables[0].retry, &variables[0].timeout);
+    assert_eq!(default_policy, RetryPolicy {
+        max_attempts: 5,
+        initial_delay: Duration::from_millis(100),
+        max_delay: Duration::from_millis(5000),
+        jitter: true,
+        timeout: Some(Duration::from_secs(30)),
+        deadline: Some(Duration::from_millis(60000))
+    });
+
+    // Input overrides
+    let custom_policy = RetryPolicy::for_input(ctx, &variables[1].retry, &variables[1].timeout);
+    assert_eq!(custom_policy, RetryPolicy {
+        max_attempts: 1,
+        initial_delay: Duration::from_millis(100),
+        max_delay: Duration::from_millis(5000),
+        jitter: false,
+        timeout: Some(Duration::from_secs(5)),
+        deadline: Some(Duration::from_millis(10000))
+    });
+
+    let file_policy = RetryPolicy::for_input(ctx, &files[0].retry, &files[0].timeout);
+    assert_eq!(file_policy.max_attempts, 5);
+    assert_eq!(file_policy.max_delay, Duration::from_millis(1000));
+
+    // Built-in defaults without config
+    let empty_ctx = test_lib::create_dummy_context();
+    let builtin_policy = RetryPolicy::for_input(&empty_ctx, &None, &None);
+    assert_eq!(builtin_policy.max_attempts, 3);
+    assert_eq!(builtin_policy.timeout, None);
+    assert_eq!(builtin_policy.deadline, None);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_optional_inputs_and_first_of() -> Result<(), anyhow::Error> {
+    test_setup().await?;
+
+    let result = load_env_dryrun_for("optional", "dev").await?;
+
+    assert!(!result.variables.contains_key("OPTIONAL_VAR"));
+    assert_eq!(result.variables.get("DEFAULT_VAR").unwrap().value, "fallback-value");
+    assert_eq!(result.variables.get("FIRST_OF_VAR").unwrap().value, "RESULT:/app/fallback");
+    assert_eq!(result.variables.get("FIRST_OF_REFERENCE").unwrap().value, "ref-fallback-value");
+
+    assert!(result.files.values().all(|f| f.variable.name != "OPTIONAL_FILE"));
+    let default_file = result.files.values().find(|f| f.variable.name == "DEFAULT_FILE").unwrap();
+    assert_eq!(default_file.content, b"default-content");
+
+    let mut fallbacks: Vec<(String, FallbackOutcome)> = result.fallbacks.iter()
+        .map(|f| (f.input.clone(), f.outcome.clone()))
+        .collect();
+    fallbacks.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(fallbacks, vec![
+        (String::from("DEFAULT_FILE"), FallbackOutcome::DefaultUsed),
+        (String::from("DEFAULT_VAR"), FallbackOutcome::DefaultUsed),
+        (String::from("OPTIONAL_FILE"), FallbackOutcome::Skipped),
+        (String::from("OPTIONAL_VAR"), FallbackOutcome::Skipped),
+    ]);
+    assert!(result.fallbacks.iter().all(|f| f.reason.contains("NOVOPS_TEST_OPTIONAL_UNSET")));
+
+    // listing outputs is a dry-run: inputs don't fail but declared fallbacks are listed
+    let listed = list_outputs_for_environment(vec![String::from("tests/.novops.optional.yml")], Some(String::from("declared"))).await?;
+    assert!(listed.fallbacks.is_empty());
+    assert_eq!(listed.declared_fallbacks, DeclaredFallbacks {
+        optional: vec![String::from("SENTRY_DSN"), String::from("EXTRA_CONFIG")],
+        defaults: vec![String::from("LOG_LEVEL")],
+        first_of: vec![String::from("API_TOKEN")],
+    });
+
+    // inputs referencing skipped variables fall back to their own optional or default
+    let result_chain = load_env_dryrun_for("optional", "chain").await?;
+    assert!(!result_chain.variables.contains_key("SKIPPED_VAR"));
+    assert!(!result_chain.variables.contains_key("SKIPPED_REFERENCE"));
+    assert!(!result_chain.variables.contains_key("SKIPPED_REFERENCE_CHAIN"));
+    assert!(result_chain.files.values().all(|f| f.variable.name != "SKIPPED_REFERENCE_FILE"));
+    assert_eq!(result_chain.variables.get("DEFAULT_REFERENCE").unwrap().value, "default-ref");
+    assert_eq!(result_chain.variables.get("DEFAULT_REFERENCE_CHAIN").unwrap().value, "ref-default-ref");
+
+    let mut chain_fallbacks: Vec<(String, FallbackOutcome)> = result_chain.fallbacks.iter()
+        .map(|f| (f.input.clone(), f.outcome.clone()))
+        .collect();
+    chain_fallbacks.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(chain_fallbacks, vec![
+        (String::from("DEFAULT_REFERENCE"), FallbackOutcome::DefaultUsed),
+        (String::from("SKIPPED_REFERENCE"), FallbackOutcome::Skipped),
+        (String::from("SKIPPED_REFERENCE_CHAIN"), FallbackOutcome::Skipped),
+        (String::from("SKIPPED_REFERENCE_FILE"), FallbackOutcome::Skipped),
+        (String::from("SKIPPED_VAR"), FallbackOutcome::Skipped),
+    ]);
+
+    let err_chain = format!("{:?}", load_env_dryrun_for("optional", "chain-required").await.expect_err("Expected referenced variable error"));
+    assert!(err_chain.contains(r#"Couldn't resolve variable input REQUIRED_REFERENCE as referenced variable(s) ["SKIPPED_VAR"] failed to resolve"#), 
+        "Unexpected error: {}", err_chain);
+
+    let result_required = load_env_dryrun_for("optional", "required").await;
+    let err_required = format!("{:?}", result_required.expect_err("Expected first_of error"));
+    assert!(err_required.contains("None of first_of Inputs could be resolved"), "Unexpected error: {}", err_required);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_aws_session_credentials_for_inputs() -> Result<(), anyhow::Error> {
+    test_setup().await?;
+
+    let result = load_env_dryrun_for("aws-session", "assume-role").await?;
+    assert_eq!(result.variables.get("CROSS_ACCOUNT_PARAM").unwrap().value, "RESULT:/cross-account/param");
+    assert_eq!(result.variables.get("AWS_ACCESS_KEY_ID").unwrap().value, "AKIADRYRUNDRYUNDRYRUN");
+
+    // assumed role credentials are resolved last and take precedence over Hashivault credentials
+    let result_chain = load_env_dryrun_for("aws-session", "chain").await?;
+    assert_eq!(result_chain.variables.get("CROSS_ACCOUNT_SECRET").unwrap().value, "RESULT:cross-account/secret");
+    assert_eq!(result_chain.variables.get("AWS_ACCESS_KEY_ID").unwrap().value, "AKIADRYRUNDRYUNDRYRUN");
+
+    // AWS Inputs use assumed role credentials, not the Hashivault credentials used to assume role
+    assert_eq!(result_chain.variables.get("AWS_CALLER_ARN").unwrap().value, 
+        "arn:aws:sts::000000000000:assumed-role/dryrun/AKIADRYRUNDRYUNDRYRUN");
+
+    let outputs: Vec<VariableOutput> = ["AWS_ACCESS_KEY_ID", "AWS_SECRET_ACCESS_KEY", "AWS_SESSION_TOKEN", "AWS_SESSION_EXPIRATION"].iter()
+        .map(|name| result.variables.get(*name).unwrap().clone())
+        .collect();
+    let credentials = session_credentials_from_outputs(&outputs)?;
+    assert_eq!(credentials.access_key_id(), "AKIADRYRUNDRYUNDRYRUN");
+    assert_eq!(credentials.session_token(), Some("xxx"));
+    assert!(credentials.expiry().is_some());
+
+    let missing_secret = session_credentials_from_outputs(&outputs[..1]);
+    assert!(missing_secret.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_assume_role_chain_dryrun() -> Result<(), anyhow::Error> {
+    test_setup().await?;
+
+    let result = load_env_dryrun_for("aws_assumerole", "chain").await?;
+    assert_eq!(result.variables.get("AWS_ACCESS_KEY_ID").unwrap().value, "AKIADRYRUNDRYUNDRYRUN");
+    assert_eq!(result.variables.get("AWS_SESSION_EXPIRATION").unwrap().value, "32472144000");
+
+    let env = result.context.config_file_data.environments.get("chain").unwrap();
+    let chain = env.aws.clone().unwrap().assume_role.unwrap().chain.unwrap();
+    assert_eq!(chain.len(), 1);
+    assert_eq!(chain[0].session.transitive_tag_keys, Some(vec![String::from("team")]));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_assume_role_session_options_dryrun() -> Result<(), anyhow::Error> {
+    test_setup().await?;
+
+    std::env::set_var("NOVOPS_TEST_MFA_TOKEN_CODE", "654321");
+
+    let result = load_env_dryrun_for("aws_assumerole", "session-options").await?;
+    assert_eq!(result.variables.get("AWS_ACCESS_KEY_ID").unwrap().value, "AKIADRYRUNDRYUNDRYRUN");
+
+    let env = result.context.config_file_data.environments.get("session-options").unwrap();
+    let assume_role = env.aws.clone().unwrap().assume_role.unwrap();
+    assert_eq!(assume_role.session.external_id, Some(String::from("novops-external-id")));
+    assert_eq!(assume_role.session.policy_arns, Some(vec![String::from("arn:aws:iam::aws:policy/ReadOnlyAccess")]));
+    assert_eq!(assume_role.mfa_serial, Some(String::from("arn:aws:iam::111122223333:mfa/novops")));
+
+    // MFA token code is resolved once before retries
+    let resolved = assume_role.with_resolved_mfa_token_code(&result.context).await?;
+    assert_eq!(resolved.mfa_token_code, Some(StringResolvableInput::String(String::from("654321"))));
+
+    let result_long_name = load_env_dryrun_for("aws_assumerole", "session-name-too-long").await;
+    let err = format!("{:?}", result_long_name.expect_err("Expected session name error"));
+    assert!(err.contains("is longer than 64 characters"), "Unexpected error: {}", err);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_assume_role_with_web_identity_dryrun() -> Result<(), anyhow::Error> {
+    test_setup().await?;
+
+    std::env::set_var("NOVOPS_TEST_OIDC_TOKEN", "eyJhbGciOiJSUzI1NiJ9.dummy");
+    fs::create_dir_all(TEST_DIR)?;
+    fs::write(PathBuf::from(TEST_DIR).join("web-identity-token"), "eyJhbGciOiJSUzI1NiJ9.dummy\n")?;
+
+    for env in ["token-env", "token-file", "token-input"] {
+        let result = load_env_dryrun_for("aws-web-identity", env).await?;
+        assert_eq!(result.variables.get("AWS_ACCESS_KEY_ID").unwrap().value, "AKIADRYRUNWEBIDENTITY", "Unexpected credentials for {}", env);
+        assert_eq!(result.variables.get("AWS_SESSION_EXPIRATION").unwrap().value, "32472144000");
+    }
+
+    let result = load_env_dryrun_for("aws-web-identity", "token-env").await?;
+    assert_eq!(result.variables.get("CI_PARAM").unwrap().value, "RESULT:/ci/param");
+
+    let err_no_token = format!("{:?}", load_env_dryrun_for("aws-web-identity", "no-token").await.expect_err("Expected missing token error"));
+    assert!(err_no_token.contains("Exactly one of `web_identity_token_file`"), "Unexpected error: {}", err_no_token);
+
+    let err_both = format!("{:?}", load_env_dryrun_for("aws-web-identity", "both-credentials").await.expect_err("Expected credentials error"));
+    assert!(err_both.contains("Exactly one of `aws.assume_role` or `aws.assume_role_with_web_identity`"), "Unexpected error: {}", err_both);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_aws_credentials_profiles() -> Result<(), anyhow::Error> {
+    test_setup().await?;
+
+    let result = load_env_dryrun_for("aws-profiles", "dev").await?;
+
+    assert!(!result.variables.contains_key("AWS_ACCESS_KEY_ID"));
+    assert_eq!(result.variables.get("AWS_PROFILE").unwrap().value, "role");
+
+    let credentials = result.files.values().find(|f| f.variable.name == "AWS_SHARED_CREDENTIALS_FILE").unwrap();
+    assert_eq!(String::from_utf8(credentials.content.clone())?, "\
+        [vault]\naws_access_key_id = AKIADRYRUNACCESSKEY\naws_secret_access_key = s3cret\naws_session_token = securityToken\n\n\
+        [role]\naws_access_key_id = AKIADRYRUNDRYUNDRYRUN\naws_secret_access_key = xxx\naws_session_token = xxx\n\n\
+        [shared]\naws_access_key_id = AKIADRYRUNDRYUNDRYRUN\naws_secret_access_key = xxx\naws_session_token = xxx\n\n\
+        [platform]\naws_access_key_id = AKIADRYRUNACCESSKEY\naws_secret_access_key = s3cret\naws_session_token = securityToken\n\n");
+
+    // region of backend used to generate credentials
+    let config = result.files.values().find(|f| f.variable.name == "AWS_CONFIG_FILE").unwrap();
+    assert_eq!(String::from_utf8(config.content.clone())?, "\
+        [profile vault]\nregion = eu-west-3\n\n[profile role]\nregion = eu-west-3\n\n\
+        [profile shared]\nregion = us-east-1\n\n[profile platform]\nregion = eu-west-3\n\n");
+
+    let result_no_default = load_env_dryrun_for("aws-profiles", "no-default").await?;
+    assert!(!result_no_default.variables.contains_key("AWS_PROFILE"));
+    assert!(result_no_default.files.values().any(|f| f.variable.name == "AWS_SHARED_CREDENTIALS_FILE"));
+
+    let err_list = format!("{:?}", load_env_dryrun_for("aws-profiles", "duplicate-list").await.expect_err("Expected duplicate profile error"));
+    assert!(err_list.contains("AWS credentials profile 'same' is used by more than one Input"), "Unexpected error: {}", err_list);
+
+    let err_default = format!("{:?}", load_env_dryrun_for("aws-profiles", "unknown-default").await.expect_err("Expected unknown default profile error"));
+    assert!(err_default.contains(r#"AWS default profile 'unknown' is not an output profile. Output profiles: ["role"]"#), "Unexpected error: {}", err_default);
+
+    let err_nested = format!("{:?}", load_env_dryrun_for("aws-profiles", "nested-options").await.expect_err("Expected nested options error"));
+    assert!(err_nested.contains("`use_for_inputs` and `credentials_profile` can't be set on Inputs of AWS credentials profile 'nested'"), "Unexpected error: {}", err_nested);
+
+    let err = format!("{:?}", load_env_dryrun_for("aws-profiles", "duplicate").await.expect_err("Expected duplicate profile error"));
+    assert!(err.contains("AWS credentials profile 'same' is used by more than one Input"), "Unexpected error: {}", err);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_aws_credential_process() -> Result<(), anyhow::Error> {
+    test_setup().await?;
+
+    let args = |conf_name: &str, env: &str| NovopsLoadArgs {
+        config: vec![format!("tests/.novops.{}.yml", conf_name)],
+        env: Some(env.to_string()),
+        working_directory: None,
+        skip_working_directory_check: Some(false),
+        dry_run: Some(true),
+    };
+
+    let assume_role = resolve_aws_credential_process(&args("aws_assumerole", "dev"), &None).await?;
+    assert_eq!(serde_json::to_string(&assume_role)?, r#"{"Version":1,"AccessKeyId":"AKIADRYRUNDRYUNDRYRUN","SecretAccessKey":"xxx","SessionToken":"xxx","Expiration":"2999-01-01T00:00:00Z"}"#);
+
+    // Hashivault credentials expire with Vault lease
+    let hashivault = resolve_aws_credential_process(&args("aws-profiles", "dev"), &Some(String::from("hashivault"))).await?;
+    assert_eq!(hashivault.access_key_id, "AKIADRYRUNACCESSKEY");
+    let expiration = hashivault.expiration.expect("Expected Hashivault credentials expiration");
+    let expiration = DateTime::from_str(&expiration, Format::DateTime)?.secs();
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+    assert!((now + 3600 - 5..=now + 3600 + 5).contains(&expiration), "Unexpected expiration: {}", expiration);
+
+    let missing = resolve_aws_credential_process(&args("optional", "dev"), &None).await;
+    let err = format!("{:?}", missing.expect_err("Expected missing input error"));
+    assert!(err.contains("No `aws` or `hashivault` Input in environment 'dev'"), "Unexpected error: {}", err);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_aws_ssm_parameters_by_path_dryrun() -> Result<(), anyhow::Error> {
+    test_setup().await?;
+
+    let result = load_env_dryrun_for("aws_ssm", "by-path").await?;
+
+    assert_eq!(result.variables.get("DRYRUN_PARAM").unwrap().value, "RESULT:/novops-test/app/dryrun_param");
+    assert_eq!(result.variables.get("NESTED_DRYRUN_PARAM").unwrap().value, "RESULT:/novops-test/app/nested/dryrun_param");
+    assert_eq!(result.variables.get("/novops-test/app/dryrun_param").unwrap().value, "RESULT:/novops-test/app/dryrun_param");
+    assert!(!result.variables.contains_key("/novops-test/app/nested/dryrun_param"));
+
+    let err = format!("{:?}", load_env_dryrun_for("aws_ssm", "by-path-duplicate").await.expect_err("Expected duplicate name error"));
+    assert!(err.contains("Several parameters under path /novops-test/dup resolved to variable name SAME"), "Unexpected error: {}", err);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_json_extract_and_expand() -> Result<(), anyhow::Error> {
+    test_setup().await?;
+
+    let result = load_env_dryrun_for("json", "dev").await?;
+
+    assert_eq!(result.variables.get("DB_PASSWORD").unwrap().value, "RESULT:myapp/db:/password");
+    assert_eq!(result.variables.get("API_KEY").unwrap().value, "RESULT:/myapp/api:/key");
+
+    assert_eq!(result.variables.get("APP_USERNAME").unwrap().value, "admin");
+    assert_eq!(result.variables.get("APP_API_KEY").unwrap().value, "s3cret");
+    assert_eq!(result.variables.get("APP_DATABASE_HOST").unwrap().value, "localhost");
+    assert_eq!(result.variables.get("DB_PORT").unwrap().value, "5432");
+    assert_eq!(result.variables.get("host").unwrap().value, "localhost");
+    assert_eq!(result.variables.get("port").unwrap().value, "5432");
+    assert_eq!(result.variables.get("CONFIG_RESULT").unwrap().value, "RESULT:myapp/config");
+
+    // listing outputs is a dry-run and succeeds with dummy secrets
+    let outputs = list_outputs_for_environment(vec![String::from("tests/.novops.json.yml")], Some(String::from("dev"))).await?;
+    assert!(outputs.variables.contains_key("DB_PASSWORD"));
+
+    let err_duplicate = format!("{:?}", load_env_dryrun_for("json", "duplicate").await.expect_err("Expected duplicate name error"));
+    assert!(err_duplicate.contains("Several JSON keys resolved to variable name API_KEY"), "Unexpected error: {}", err_duplicate);
+
+    let err_not_object = format!("{:?}", load_env_dryrun_for("json", "not-object").await.expect_err("Expected not an object error"));
+    assert!(err_not_object.contains("Expected a JSON object to expand, got: array"), "Unexpected error: {}", err_not_object);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_transform_inputs() -> Result<(), anyhow::Error> {
+    test_setup().await?;
+
+    let result = load_env_dryrun_for("transform", "dev").await?;
+
+    assert_eq!(result.variables.get("TRIMMED").unwrap().value, "aGVsbG8=");
+    assert_eq!(result.variables.get("DECODED").unwrap().value, "hello");
+    assert_eq!(result.variables.get("CONFIG_YAML").unwrap().value, "---\nfoo: bar\n");
+    assert_eq!(result.variables.get("DRYRUN_SECRET").unwrap().value, "RESULT:cert");
+
+    // listing outputs is a dry-run and succeeds with dummy secrets which can't be decoded
+    let outputs = list_outputs_for_environment(vec![String::from("tests/.novops.transform.yml")], Some(String::from("dev"))).await?;
+    assert!(outputs.variables.contains_key("DRYRUN_SECRET"));
+
+    let file = result.files.values().find(|f| f.variable.name == "BINARY_FILE").unwrap();
+    assert_eq!(file.content, vec![240, 159, 146, 150, 255]);
+
+    let err = format!("{:?}", load_env_dryrun_for("transform", "not-utf8").await.expect_err("Expected UTF-8 error"));
+    assert!(err.contains("Transformed value is not a valid UTF-8 string"), "Unexpected error: {}", err);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_aws_s3_object_options_dryrun() -> Result<(), anyhow::Error> {
+    test_setup().await?;
+
+    let result = load_env_dryrun_for("aws_s3_object", "options").await?;
+
+    let file = result.files.values().find(|f| f.variable.name == "S3_OBJECT_OPTIONS").unwrap();
+    assert_eq!(file.content, b"dummy");
+
+    let err = format!("{:?}", load_env_dryrun_for("aws_s3_object", "invalid-sse-customer-key").await.expect_err("Expected invalid key error"));
+    assert!(err.contains("SSE-C customer key must be a 256-bit key, got 72 bits"), "Unexpected error: {}", err);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_hvault_kv2_all_dryrun() -> Result<(), anyhow::Error> {
+    test_setup().await?;
+
+    let result = load_env_dryrun_for("hvault_kv2", "all").await?;
+
+    assert_eq!(result.variables.get("HASHIVAULT_KV_V2_VERSIONED").unwrap().value, "RESULT:test_hashivault_kv2/novops_secret");
+    assert_eq!(result.variables.get("DRYRUN_KEY").unwrap().value, "RESULT:test_hashivault_kv2/dryrun_key");
+    assert_eq!(result.variables.get("FOLDER_DRYRUN_SECRET_DRYRUN_KEY").unwrap().value, 
+        "RESULT:test_hashivault_kv2_folder/dryrun_secret/dryrun_key");
+    assert!(!result.variables.keys().any(|k| k.contains("DRYRUN_FOLDER")), "Nested folder should be ignored");
+
+    let err = format!("{:?}", load_env_dryrun_for("hvault_kv2", "all-list-version").await.expect_err("Expected list with version error"));
+    assert!(err.contains("`version` can't be used with `list` for folder test_hashivault_kv2_folder"), "Unexpected error: {}", err);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_aws_sts_caller_identity_dryrun() -> Result<(), anyhow::Error> {
+    test_setup().await?;
+
+    let result = load_env_dryrun_for("aws_caller_identity", "dev").await?;
+    assert_eq!(result.variables.get("AWS_ACCOUNT_ID").unwrap().value, "000000000000");
+    assert_eq!(result.variables.get("AWS_CALLER_ARN").unwrap().value, "arn:aws:iam::000000000000:user/dryrun");
+    assert_eq!(result.variables.get("AWS_REGION").unwrap().value, "dryrun");
+
+    let result = load_env_dryrun_for("aws_caller_identity", "renamed").await?;
+    assert_eq!(result.variables.get("ACCOUNT_ID").unwrap().value, "000000000000");
+    assert_eq!(result.variables.get("CALLER_ARN").unwrap().value, "arn:aws:iam::000000000000:user/dryrun");
+    assert_eq!(result.variables.get("DEPLOY_REGION").unwrap().value, "dryrun");
+    assert!(!result.variables.contains_key("AWS_ACCOUNT_ID"));
+
+    let result = load_env_dryrun_for("aws_caller_identity", "assume-role").await?;
+    assert_eq!(result.variables.get("AWS_CALLER_ARN").unwrap().value, "arn:aws:sts::000000000000:assumed-role/dryrun/AKIADRYRUNDRYUNDRYRUN");
+
+    let result = load_env_dryrun_for("aws_caller_identity", "assume-role-output-only").await?;
+    assert_eq!(result.variables.get("AWS_CALLER_ARN").unwrap().value, "arn:aws:iam::000000000000:user/dryrun");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_aws_sso_config_dryrun() -> Result<(), anyhow::Error> {
+    test_setup().await?;
+
+    // dry-run never authorizes SSO access
+    let result = load_env_dryrun_for("aws_sso", "dev").await?;
+
+    assert_eq!(result.variables.get("SSM_PARAM").unwrap().value, "RESULT:/app/param");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_aws_tokens_dryrun() -> Result<(), anyhow::Error> {
+    test_setup().await?;
+
+    let result = load_env_dryrun_for("aws-tokens", "dev").await?;
+
+    assert_eq!(result.variables.get("ECR_PASSWORD").unwrap().value, "RESULT:ecr-password");
+    assert_eq!(result.variables.get("CODEARTIFACT_AUTH_TOKEN").unwrap().value, "RESULT:my-domain");
+    assert_eq!(result.variables.get("EKS_TOKEN").unwrap().value, "k8s-aws-v1.RESULT:my-cluster");
+    assert_eq!(result.variables.get("DB_PASSWORD").unwrap().value, 
+        "mydb.123456789012.eu-west-1.rds.amazonaws.com:5432/?Action=connect&DBUser=app_user&RESULT");
+    assert_eq!(result.variables.get("DATABASE_URL").unwrap().value, "postgres://app_user:\
+        mydb.123456789012.eu-west-1.rds.amazonaws.com%3A5432%2F%3FAction%3Dconnect%26DBUser%3Dapp_user%26RESULT\
+        @mydb.123456789012.eu-west-1.rds.amazonaws.com:5432/app");
+
+    let docker_config = result.files.values().find(|f| f.variable.name == "DOCKER_CONFIG_FILE").unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&docker_config.content)?;
+    assert_eq!(json, serde_json::json!({
+        "auths": { "000000000000.dkr.ecr.dryrun.amazonaws.com": { "auth": "QVdTOlJFU1VMVDplY3ItcGFzc3dvcmQ=" } }
+    }));
+
+    let docker_config_registries = result.files.values().find(|f| f.variable.name == "DOCKER_CONFIG_REGISTRIES").unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&docker_config_registries.content)?;
+    assert_eq!(json["auths"].as_object().unwrap().keys().collect::<Vec<_>>(), 
+        vec!["111122223333.dkr.ecr.eu-west-1.amazonaws.com", "444455556666.dkr.ecr.eu-west-1.amazonaws.com"]);
+
+    Ok(())
+}
@@ -19,8 +19,7 @@ use kube;
 use novops::core::NovopsContext;
 
 fn extract_vault_config(ctx: &NovopsContext) -> HashivaultConfig  {
-    ctx.config_file_data.config.clone().unwrap_or_default()
-        .hashivault.unwrap_or_default()
+    ctx.config.hashivault.clone().unwrap_or_default()
 }
 
 #[tokio::test]
@@ -50,6 +49,21 @@ async fn test_hashivault_kv2() -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_hashivault_kv2_all() -> Result<(), anyhow::Error> {
+    test_setup().await?;
+
+    let outputs = load_env_for("hvault_kv2", "all").await?;
+
+    assert_eq!(outputs.variables.get("HASHIVAULT_KV_V2_VERSIONED").unwrap().value, "s3cret_kv2");
+    assert_eq!(outputs.variables.get("NOVOPS_SECRET").unwrap().value, "s3cret_kv2");
+    assert_eq!(outputs.variables.get("FOLDER_APP_USER").unwrap().value, "novops");
+    assert_eq!(outputs.variables.get("FOLDER_APP_DB_PASSWORD").unwrap().value, "s3cret_kv2_folder");
+    assert!(!outputs.variables.contains_key("FOLDER_OTHER_IGNORED"));
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_hashivault_kv1() -> Result<(), Error> {
     test_setup().await?;
@@ -99,6 +113,7 @@ async fn test_hashivault_aws() -> Result<(), Error> {
     assert!(!outputs.variables.get("AWS_ACCESS_KEY_ID").unwrap().value.is_empty());
     assert!(!outputs.variables.get("AWS_SECRET_ACCESS_KEY").unwrap().value.is_empty());
     assert!(!outputs.variables.get("AWS_SESSION_TOKEN").unwrap().value.is_empty());
+    assert!(!outputs.variables.get("AWS_SESSION_EXPIRATION").unwrap().value.is_empty());
 
     Ok(())
 }
@@ -397,7 +412,8 @@ fn create_dummy_context_with_hvault(addr: Option<String>, token: Option<String>,
         namespace: None
     }), ..Default::default() };
 
-    ctx.config_file_data.config = Some(novops_config);
+    ctx.config_file_data.config = Some(novops_config.clone());
+    ctx.config = novops_config;
 
     ctx
 }
@@ -405,7 +421,7 @@ fn create_dummy_context_with_hvault(addr: Option<String>, token: Option<String>,
 fn create_dummy_auth_context(addr: Option<String>, auth: HashiVaultAuth, token: Option<String>, token_path: Option<PathBuf>) -> NovopsContext {
     let mut context = create_dummy_context_with_hvault(addr, token, token_path);
 
-    let config = context.config_file_data.config.as_mut().unwrap().hashivault.as_mut().unwrap();
+    let config = context.config.hashivault.as_mut().unwrap();
     config.auth = Some(auth);
 
     context
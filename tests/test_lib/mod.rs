@@ -1,7 +1,8 @@
 use log::debug;
 use novops::core::{NovopsConfig, NovopsConfigDefault, NovopsConfigFile, NovopsContext};
-use novops::modules::aws::{client::get_iam_client, config::AwsClientConfig};
+use novops::modules::aws::{client::{get_iam_client, get_ssm_client}, config::AwsClientConfig};
 use novops::{load_context_and_resolve, NovopsLoadArgs, NovopsOutputs};
+use novops::clients::ClientRegistry;
 use std::collections::HashMap;
 use std::env;
 use std::fs;
@@ -52,7 +53,7 @@ async fn _load_env_for(
     dry_run: bool,
 ) -> Result<NovopsOutputs, anyhow::Error> {
     let args = NovopsLoadArgs {
-        config: Some(format!("tests/.novops.{}.yml", conf_name)),
+        config: vec![format!("tests/.novops.{}.yml", conf_name)],
         env: Some(env.to_string()),
         working_directory: None,
         skip_working_directory_check: Some(false),
@@ -104,6 +105,28 @@ pub fn aws_test_config() -> AwsClientConfig {
     aws_conf
 }
 
+/**
+ * create SSM parameter with a version for each value, delete it first if already exists
+ */
+#[allow(dead_code)]
+pub async fn aws_ensure_ssm_parameter_versions(name: &str, values: &[&str]) -> Result<(), anyhow::Error> {
+    let client = get_ssm_client(&aws_test_config()).await?;
+
+    // ignore error if parameter doesn't exist
+    let _ = client.delete_parameter().name(name).send().await;
+
+    for value in values {
+        client.put_parameter()
+            .name(name)
+            .value(*value)
+            .r#type(aws_sdk_ssm::types::ParameterType::String)
+            .overwrite(true)
+            .send().await?;
+    }
+
+    Ok(())
+}
+
 /**
  * create test IAM role to impersonate, delete it first if already exists
  */
@@ -149,16 +172,33 @@ pub fn create_dummy_context() -> NovopsContext {
         workdir: PathBuf::from("/tmp"),
         config_file_data: NovopsConfigFile {
             name: Some(String::from("test-empty")),
+            include: None,
             environments: HashMap::new(),
             config: Some(NovopsConfig {
                 default: Some(NovopsConfigDefault {
                     environment: Some(String::from("dev")),
+                    retry: None,
+                    timeout: None,
                 }),
                 hashivault: None,
                 aws: None,
+                backends: None,
+            }),
+        },
+        config: NovopsConfig {
+            default: Some(NovopsConfigDefault {
+                environment: Some(String::from("dev")),
+                retry: None,
+                timeout: None,
             }),
+            hashivault: None,
+            aws: None,
+            backends: None,
         },
         env_var_filepath: PathBuf::from("/tmp/vars"),
         dry_run: false,
+        resolved_variables: HashMap::new(),
+        clients: ClientRegistry::default(),
+        aws_session_credentials: None
     }
 }